@@ -1,19 +1,39 @@
 #[cfg(feature = "futures")]
 use futures::IntoFuture;
 
+#[cfg(all(feature = "futures", feature = "serde"))]
+use futures::io::AsyncRead;
+
+#[cfg(all(feature = "futures", feature = "serde"))]
+use futures::task::Context;
+
+#[cfg(all(feature = "futures", feature = "serde"))]
+use futures::{Async, Future, Poll};
+
+#[cfg(all(feature = "futures", feature = "serde"))]
+use std::io;
+
+#[cfg(all(feature = "futures", feature = "serde"))]
+use std::marker::PhantomData;
+
 #[cfg(all(feature = "ron", feature = "serde"))]
 use ron;
 
 #[cfg(feature = "serde")]
 use serde::de::DeserializeOwned;
 
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
 #[cfg(feature = "gfx-render")]
 use hal::Backend;
 
 #[cfg(feature = "gfx-render")]
 use render::Factory;
 
-use std::io::Read;
+use std::io::{Read, Seek, Write};
+
+use store::Bytes;
 
 pub trait AssetLoaderKind {
     const KIND: &'static str;
@@ -37,6 +57,34 @@ pub trait AssetLoader<A, F>: AssetLoaderKind {
         R: Read;
 }
 
+/// `SeekAssetLoader` loads assets from raw data with random access.
+/// Implemented by loaders of formats that need to jump around the data.
+pub trait SeekAssetLoader<A, F>: AssetLoader<A, F> {
+    /// Load asset from seekable raw data.
+    fn load_seekable<R>(&mut self, format: F, reader: R) -> Result<A, Self::Error>
+    where
+        R: Read + Seek;
+}
+
+/// `BytesAssetLoader` loads assets from raw data in memory.
+/// Data can be memory-mapped file, so loader can avoid copying it.
+pub trait BytesAssetLoader<A, F>: AssetLoader<A, F> {
+    /// Load asset from raw data in memory.
+    fn load_bytes(&mut self, format: F, bytes: Bytes) -> Result<A, Self::Error>;
+}
+
+/// `AssetSaver` saves assets into raw data.
+/// Counterpart of `AssetLoader`, usually implemented by the same type.
+pub trait AssetSaver<A, F>: AssetLoaderKind {
+    /// Possible error type.
+    type Error;
+
+    /// Save asset into raw data.
+    fn save<W>(&mut self, format: F, asset: &A, writer: W) -> Result<(), Self::Error>
+    where
+        W: Write;
+}
+
 #[cfg(feature = "futures")]
 /// `AssetStreamingLoader` can load assets from data chunks.
 pub trait AsyncAssetLoader<A, F, R>: AssetLoader<A, F> + Sized {
@@ -74,6 +122,7 @@ where
 }
 
 #[cfg(feature = "serde")]
+#[derive(Clone, Copy, Debug)]
 pub struct SerdeLoader;
 
 #[cfg(feature = "serde")]
@@ -108,7 +157,101 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
+pub trait SerdeSaveFormat {
+    type Error;
+
+    fn to_writer<S, W>(self, value: &S, writer: W) -> Result<(), Self::Error>
+    where
+        S: Serialize,
+        W: Write;
+}
+
+#[cfg(feature = "serde")]
+impl<A, F> AssetSaver<A, F> for SerdeLoader
+where
+    A: Serialize,
+    F: SerdeSaveFormat,
+{
+    type Error = F::Error;
+
+    fn save<W>(&mut self, format: F, asset: &A, writer: W) -> Result<(), Self::Error>
+    where
+        W: Write,
+    {
+        debug!("Saving asset with serde");
+        format.to_writer(asset, writer)
+    }
+}
+
+#[cfg(all(feature = "futures", feature = "serde"))]
+impl<A, F, R> AsyncAssetLoader<A, F, R> for SerdeLoader
+where
+    A: DeserializeOwned,
+    F: SerdeFormat,
+    F::Error: From<io::Error>,
+    R: AsyncRead,
+{
+    type Loader = SerdeLoading<A, F, R>;
+
+    fn load_async(self, format: F, reader: R) -> SerdeLoading<A, F, R> {
+        SerdeLoading {
+            state: Some((self, format, reader)),
+            data: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Future of asset loaded by `SerdeLoader` from asynchronous reader.
+/// Data is collected without blocking and deserialized when reader is exhausted.
+#[cfg(all(feature = "futures", feature = "serde"))]
+pub struct SerdeLoading<A, F, R> {
+    state: Option<(SerdeLoader, F, R)>,
+    data: Vec<u8>,
+    marker: PhantomData<fn() -> A>,
+}
+
+#[cfg(all(feature = "futures", feature = "serde"))]
+impl<A, F, R> Future for SerdeLoading<A, F, R>
+where
+    A: DeserializeOwned,
+    F: SerdeFormat,
+    F::Error: From<io::Error>,
+    R: AsyncRead,
+{
+    type Item = (SerdeLoader, A);
+    type Error = (SerdeLoader, F::Error);
+
+    fn poll(&mut self, cx: &mut Context) -> Poll<(SerdeLoader, A), (SerdeLoader, F::Error)> {
+        let mut chunk = [0; 8192];
+        let result = loop {
+            let reader = &mut self
+                .state
+                .as_mut()
+                .expect("Future polled after completion")
+                .2;
+            match reader.poll_read(cx, &mut chunk) {
+                Ok(Async::Ready(0)) => break Ok(()),
+                Ok(Async::Ready(read)) => self.data.extend_from_slice(&chunk[..read]),
+                Ok(Async::Pending) => return Ok(Async::Pending),
+                Err(err) => break Err(err),
+            }
+        };
+
+        let (loader, format, _) = self.state.take().unwrap();
+        match result {
+            Ok(()) => match format.from_reader(&self.data[..]) {
+                Ok(asset) => Ok(Async::Ready((loader, asset))),
+                Err(err) => Err((loader, err)),
+            },
+            Err(err) => Err((loader, err.into())),
+        }
+    }
+}
+
 #[cfg(all(feature = "ron", feature = "serde"))]
+#[derive(Clone, Copy, Debug)]
 pub struct RonFormat;
 
 #[cfg(all(feature = "ron", feature = "serde"))]
@@ -123,3 +266,19 @@ impl SerdeFormat for RonFormat {
         ron::de::from_reader(reader)
     }
 }
+
+#[cfg(all(feature = "ron", feature = "serde"))]
+impl SerdeSaveFormat for RonFormat {
+    type Error = ron::ser::Error;
+
+    fn to_writer<S, W>(self, value: &S, mut writer: W) -> Result<(), Self::Error>
+    where
+        S: Serialize,
+        W: Write,
+    {
+        let text = ron::ser::to_string_pretty(value, Default::default())?;
+        writer
+            .write_all(text.as_bytes())
+            .map_err(|err| ron::ser::Error::Message(err.to_string()))
+    }
+}
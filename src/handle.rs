@@ -1,8 +1,20 @@
-use std::cell::UnsafeCell;
+use std::any::{Any, TypeId};
+use std::cell::{Cell, UnsafeCell};
+use std::cmp::Ordering as CmpOrdering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 use std::mem::ManuallyDrop;
 use std::ops::Deref;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use failure::Error;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use asset::Asset;
 
 struct Inner<T> {
     value: ManuallyDrop<UnsafeCell<T>>,
@@ -42,6 +54,61 @@ impl<T> Inner<T> {
     }
 }
 
+// Value is written only once before `ready` flag is set
+// and never mutated after that.
+unsafe impl<T> Send for Inner<T> where T: Send + Sync {}
+unsafe impl<T> Sync for Inner<T> where T: Send + Sync {}
+
+/// Type-erased `Arc<Inner<T>>` that keeps referenced value alive.
+/// Unlike `Arc<Any>` it doesn't require `T: 'static`.
+struct Owner {
+    inner: *const (),
+    retain: unsafe fn(*const ()),
+    release: unsafe fn(*const ()),
+}
+
+impl Owner {
+    fn new<T>(inner: Arc<Inner<T>>) -> Self {
+        unsafe fn retain<T>(inner: *const ()) {
+            use std::mem::forget;
+            let inner = Arc::from_raw(inner as *const Inner<T>);
+            forget(Arc::clone(&inner));
+            forget(inner);
+        }
+
+        unsafe fn release<T>(inner: *const ()) {
+            drop(Arc::from_raw(inner as *const Inner<T>));
+        }
+
+        Owner {
+            inner: Arc::into_raw(inner) as *const (),
+            retain: retain::<T>,
+            release: release::<T>,
+        }
+    }
+}
+
+impl Clone for Owner {
+    fn clone(&self) -> Self {
+        unsafe {
+            (self.retain)(self.inner);
+        }
+        Owner {
+            inner: self.inner,
+            retain: self.retain,
+            release: self.release,
+        }
+    }
+}
+
+impl Drop for Owner {
+    fn drop(&mut self) {
+        unsafe {
+            (self.release)(self.inner);
+        }
+    }
+}
+
 impl<T> Drop for Inner<T> {
     fn drop(&mut self) {
         use std::mem::needs_drop;
@@ -72,7 +139,7 @@ impl<T> Slot<T> {
         unsafe {
             self.inner.store(value);
         }
-        Handle { inner: self.inner }
+        Handle::from_inner(self.inner)
     }
 
     /// Get token for the value that will be stored.
@@ -95,34 +162,317 @@ impl<T> Token<T> {
     /// Returns `None` otherwise.
     pub fn handle(&self) -> Option<Handle<T>> {
         if self.inner.ready() {
-            Some(Handle {
-                inner: Arc::clone(&self.inner),
-            })
+            Some(Handle::from_inner(Arc::clone(&self.inner)))
         } else {
             None
         }
     }
 }
 
+/// Identifier of the value referenced by `Handle`.
+/// Stays the same for all clones of the handle and is never reused while any of them is alive.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HandleId(usize);
+
 /// Handle to the value stored through `Slot`.
-#[derive(Clone)]
+/// Handles are compared and hashed by identity of the referenced value, not by value itself.
 pub struct Handle<T> {
-    inner: Arc<Inner<T>>,
+    value: *const T,
+    owner: Owner,
+    // Value is dropped together with the last handle.
+    marker: PhantomData<T>,
+}
+
+// `owner` keeps value alive and value is never mutated.
+// Owners of mapped handles are required to be `Send + Sync + 'static` by `Handle::map`.
+unsafe impl<T> Send for Handle<T> where T: Send + Sync {}
+unsafe impl<T> Sync for Handle<T> where T: Send + Sync {}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle {
+            value: self.value,
+            owner: self.owner.clone(),
+            marker: PhantomData,
+        }
+    }
 }
 
 impl<T> Handle<T> {
     /// Create new handle with value.
     /// This is almost identical to creating `Arc`.
     pub fn new(value: T) -> Self {
+        Handle::from_inner(Arc::new(Inner::full(value)))
+    }
+
+    fn from_inner(inner: Arc<Inner<T>>) -> Self {
         Handle {
-            inner: Arc::new(Inner::full(value)),
+            value: unsafe { inner.get() },
+            owner: Owner::new(inner),
+            marker: PhantomData,
         }
     }
+
+    /// Get identifier of the referenced value.
+    pub fn id(&self) -> HandleId {
+        HandleId(self.value as usize)
+    }
+
+    /// Check if two handles reference the same value.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+
+    /// Make handle to the part of referenced value.
+    /// Returned handle keeps the whole value alive,
+    /// so the value must be `'static` to outlive handles to its parts.
+    ///
+    /// Only handles to whole assets have asset ids.
+    /// Handle to a part can't be serialized unless `f` returns the whole value.
+    pub fn map<U, F>(&self, f: F) -> Handle<U>
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(&T) -> &U,
+    {
+        Handle {
+            value: f(&**self),
+            owner: self.owner.clone(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Make handle to the part of referenced value if there is one.
+    /// Same restrictions as for `map` apply.
+    pub fn try_map<U, F>(&self, f: F) -> Option<Handle<U>>
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(&T) -> Option<&U>,
+    {
+        f(&**self).map(|value| Handle {
+            value,
+            owner: self.owner.clone(),
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other)
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        self.id().hash(state)
+    }
+}
+
+impl<T> PartialOrd for Handle<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Handle<T> {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.id().cmp(&other.id())
+    }
+}
+
+impl<T> fmt::Debug for Handle<T>
+where
+    T: Asset,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = fmt.debug_struct("Handle");
+        // Asset id is known only while handle context is set.
+        match asset_id(self) {
+            Some(id) => debug.field("id", &id),
+            None => debug.field("id", &self.id()),
+        };
+        debug.field("kind", &T::KIND).finish()
+    }
 }
 
 impl<T> Deref for Handle<T> {
     type Target = T;
     fn deref(&self) -> &T {
-        unsafe { self.inner.get() }
+        unsafe { &*self.value }
+    }
+}
+
+struct SwapInner<T> {
+    handle: RwLock<Handle<T>>,
+    version: AtomicUsize,
+}
+
+/// Shared handle which target can be replaced.
+/// Each replacement increments version number,
+/// so users can cheaply check if value was changed since last access.
+pub struct SwapHandle<T> {
+    inner: Arc<SwapInner<T>>,
+}
+
+impl<T> Clone for SwapHandle<T> {
+    fn clone(&self) -> Self {
+        SwapHandle {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> SwapHandle<T> {
+    /// Create new swappable handle with initial target.
+    pub fn new(handle: Handle<T>) -> Self {
+        SwapHandle {
+            inner: Arc::new(SwapInner {
+                handle: RwLock::new(handle),
+                version: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Get handle to the current target.
+    pub fn get(&self) -> Handle<T> {
+        self.inner.handle.read().unwrap().clone()
+    }
+
+    /// Get handle to the current target together with its version.
+    pub fn get_versioned(&self) -> (Handle<T>, usize) {
+        let handle = self.inner.handle.read().unwrap();
+        (handle.clone(), self.inner.version.load(Ordering::Acquire))
+    }
+
+    /// Get current version.
+    /// Version starts at 0 and incremented each time target is replaced.
+    pub fn version(&self) -> usize {
+        self.inner.version.load(Ordering::Acquire)
+    }
+
+    /// Replace target with new one.
+    /// Returns previous target.
+    pub fn swap(&self, handle: Handle<T>) -> Handle<T> {
+        use std::mem::replace;
+        let mut lock = self.inner.handle.write().unwrap();
+        let old = replace(&mut *lock, handle);
+        self.inner.version.fetch_add(1, Ordering::AcqRel);
+        old
+    }
+
+    /// Replace target with new value.
+    /// Returns previous target.
+    pub fn replace(&self, value: T) -> Handle<T> {
+        self.swap(Handle::new(value))
+    }
+}
+
+/// Context in which handles are serialized and deserialized as asset ids.
+/// `Assets` is the context while it loads assets.
+/// Use `with_context` to serialize or deserialize handles outside of loading.
+pub trait HandleContext {
+    /// Get id of the asset of type `kind` referenced by handle.
+    fn asset_id(&self, kind: TypeId, handle: HandleId) -> Option<String>;
+
+    /// Request asset of type `kind` by id.
+    /// Returns `Handle` to the asset boxed into `Any`.
+    fn request(&mut self, kind: TypeId, id: &str) -> Result<Box<Any>, Error>;
+}
+
+thread_local! {
+    static CONTEXT: Cell<Option<*mut HandleContext>> = Cell::new(None);
+}
+
+/// Run closure with handle context set for current thread.
+/// Previous context is restored after closure returns.
+pub fn with_context<C, F, R>(context: &mut C, f: F) -> R
+where
+    C: HandleContext,
+    F: FnOnce() -> R,
+{
+    use std::mem::transmute;
+
+    struct Restore(Option<*mut HandleContext>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CONTEXT.with(|cell| cell.set(self.0));
+        }
+    }
+
+    let context: &mut HandleContext = context;
+    // Pointer is only dereferenced while `context` is borrowed by this function.
+    let context: *mut HandleContext = unsafe { transmute(context) };
+    let _restore = Restore(CONTEXT.with(|cell| cell.replace(Some(context))));
+    f()
+}
+
+/// Request asset from current handle context.
+pub fn request<T>(id: &str) -> Result<Handle<T>, Error>
+where
+    T: Asset,
+{
+    use failure::err_msg;
+
+    let context = CONTEXT
+        .with(|cell| cell.get())
+        .ok_or_else(|| err_msg("Handle context is not set"))?;
+    let handle = unsafe { &mut *context }.request(TypeId::of::<T>(), id)?;
+    handle
+        .downcast::<Handle<T>>()
+        .map(|handle| *handle)
+        .map_err(|_| err_msg(format!("Context returned wrong handle type for <{}>", T::KIND)))
+}
+
+/// Get id of the asset referenced by handle from current handle context.
+pub fn asset_id<T>(handle: &Handle<T>) -> Option<String>
+where
+    T: Asset,
+{
+    let context = CONTEXT.with(|cell| cell.get())?;
+    unsafe { &*context }.asset_id(TypeId::of::<T>(), handle.id())
+}
+
+#[cfg(feature = "serde")]
+impl<T> Serialize for Handle<T>
+where
+    T: Asset,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::Error;
+        match asset_id(self) {
+            Some(id) => serializer.serialize_str(&id),
+            None => Err(S::Error::custom(format!(
+                "Id of the <{}> asset is unknown to the handle context",
+                T::KIND
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> Deserialize<'de> for Handle<T>
+where
+    T: Asset,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let id = String::deserialize(deserializer)?;
+        request(&id).map_err(|err| {
+            // Causes are kept in the message as deserializer errors can't carry them.
+            let causes: Vec<String> = err.iter_chain().map(|cause| cause.to_string()).collect();
+            D::Error::custom(causes.join(": "))
+        })
     }
 }
@@ -0,0 +1,164 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use path::{Case, VirtualPath};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+lazy_static! {
+    static ref INTERNER: RwLock<HashSet<&'static str>> = RwLock::new(HashSet::new());
+}
+
+/// Interned asset identifier.
+/// Cheap to copy, compare and hash.
+/// Each unique string gets its own id that lives until the end of the program.
+///
+/// Ids are normalized as `VirtualPath`s before interning,
+/// so differently spelled paths to the same asset get the same id.
+///
+/// Id holds interned string itself, so getting it back takes no lookup.
+/// Ids are compared and hashed by address of the interned string.
+#[derive(Clone, Copy)]
+pub struct AssetId(&'static str);
+
+impl AssetId {
+    /// Get id for the path.
+    /// Interns normalized path if it wasn't seen before.
+    pub fn new(id: &str) -> Self {
+        // Interned strings are normalized already, so their ids are found without normalizing.
+        if let Some(&interned) = INTERNER.read().unwrap().get(id) {
+            return AssetId(interned);
+        }
+        VirtualPath::new(id).id()
+    }
+
+    /// Get id for the path normalized with specified case policy.
+    pub fn with_case(id: &str, case: Case) -> Self {
+        VirtualPath::with_case(id, case).id()
+    }
+
+    /// Get id for the string as is.
+    pub(crate) fn intern(id: &str) -> Self {
+        if let Some(&interned) = INTERNER.read().unwrap().get(id) {
+            return AssetId(interned);
+        }
+
+        let mut interner = INTERNER.write().unwrap();
+        if let Some(&interned) = interner.get(id) {
+            return AssetId(interned);
+        }
+
+        let interned: &'static str = Box::leak(id.to_owned().into_boxed_str());
+        interner.insert(interned);
+        AssetId(interned)
+    }
+
+    /// Get id string.
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+// Each string is interned once, so equal ids share the address.
+impl PartialEq for AssetId {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_ptr() == other.0.as_ptr()
+    }
+}
+
+impl Eq for AssetId {}
+
+impl Hash for AssetId {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        (self.0.as_ptr() as usize).hash(state)
+    }
+}
+
+impl PartialOrd for AssetId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AssetId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(other.0)
+    }
+}
+
+impl fmt::Debug for AssetId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "AssetId({:?})", self.as_str())
+    }
+}
+
+impl fmt::Display for AssetId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+impl AsRef<str> for AssetId {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<Path> for AssetId {
+    fn as_ref(&self) -> &Path {
+        Path::new(self.as_str())
+    }
+}
+
+impl<'a> From<&'a str> for AssetId {
+    fn from(id: &'a str) -> Self {
+        AssetId::new(id)
+    }
+}
+
+impl From<String> for AssetId {
+    fn from(id: String) -> Self {
+        AssetId::new(&id)
+    }
+}
+
+impl<'a> From<&'a Path> for AssetId {
+    fn from(path: &'a Path) -> Self {
+        VirtualPath::from_path(path).id()
+    }
+}
+
+impl From<PathBuf> for AssetId {
+    fn from(path: PathBuf) -> Self {
+        AssetId::from(path.as_path())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for AssetId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for AssetId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let id = String::deserialize(deserializer)?;
+        Ok(AssetId::new(&id))
+    }
+}
@@ -1,9 +1,18 @@
+#[cfg(feature = "blake3")]
+extern crate blake3;
+#[cfg(feature = "chacha20poly1305")]
+extern crate chacha20poly1305;
 #[macro_use]
 extern crate derivative;
 extern crate failure;
 #[macro_use]
+extern crate lazy_static;
+#[macro_use]
 extern crate log;
 
+#[cfg(feature = "flate2")]
+extern crate flate2;
+
 #[cfg(feature = "futures")]
 extern crate futures;
 
@@ -19,6 +28,12 @@ extern crate gfx_texture;
 #[cfg(feature = "gfx-render")]
 extern crate gfx_render as render;
 
+#[cfg(feature = "lz4")]
+extern crate lz4;
+
+#[cfg(feature = "memmap")]
+extern crate memmap;
+
 #[cfg(feature = "obj")]
 extern crate obj;
 
@@ -32,6 +47,9 @@ extern crate ron;
 #[macro_use]
 extern crate serde;
 
+#[cfg(feature = "zstd")]
+extern crate zstd;
+
 #[cfg(feature = "mesh")]
 mod mesh;
 
@@ -43,12 +61,16 @@ mod texture;
 
 mod asset;
 mod handle;
+mod id;
 mod manager;
+mod path;
 mod store;
 
 pub use asset::*;
 pub use handle::*;
+pub use id::*;
 pub use manager::*;
+pub use path::*;
 pub use store::*;
 
 #[cfg(feature = "mesh")]
@@ -1,46 +1,280 @@
+mod redirect;
+
+pub use self::redirect::Redirects;
+
 use std::any::{Any, TypeId};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::io::Read;
-use std::path::PathBuf;
+use std::io::{self, Cursor, Read};
+use std::marker::PhantomData;
+use std::path::Path;
 use std::sync::Arc;
 
 use failure::Error;
 
-use asset::{Asset, AssetLoader, AssetLoaderKind};
-use store::Store;
+use asset::{
+    Asset, AssetLoader, AssetLoaderKind, AssetSaver, BytesAssetLoader, SeekAssetLoader,
+};
+use handle::{with_context, Handle, HandleContext, HandleId};
+use id::AssetId;
+use path::Glob;
+use store::{is_not_found, AssetSink, Bytes, Metadata, SeekRead, Store};
+
+#[cfg(feature = "content")]
+use store::ContentHash;
 
 trait AnyStore<I> {
-    fn fetch(&mut self, id: &I) -> Result<&mut Read, Error>;
-    fn close(&mut self);
+    fn fetch(&mut self, id: &I) -> Result<Box<Read>, Error>;
+
+    fn fetch_seekable(&mut self, id: &I) -> Result<Option<Box<SeekRead>>, Error>;
+
+    fn fetch_bytes(&mut self, id: &I) -> Result<Option<Bytes>, Error>;
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, Error>;
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, Error>;
 }
 
-impl<I, S> AnyStore<I> for (S, Option<S::Reader>)
+impl<I, S> AnyStore<I> for S
 where
     S: Store<I>,
     S::Error: Into<Error>,
+    S::Reader: 'static,
+{
+    fn fetch(&mut self, id: &I) -> Result<Box<Read>, Error> {
+        let reader = Store::fetch(self, id).map_err(|e| fetch_error(e, S::KIND))?;
+        Ok(Box::new(reader))
+    }
+
+    fn fetch_seekable(&mut self, id: &I) -> Result<Option<Box<SeekRead>>, Error> {
+        Store::fetch_seekable(self, id).map_err(|e| fetch_error(e, S::KIND))
+    }
+
+    fn fetch_bytes(&mut self, id: &I) -> Result<Option<Bytes>, Error> {
+        Store::fetch_bytes(self, id).map_err(|e| fetch_error(e, S::KIND))
+    }
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, Error> {
+        Store::metadata(self, id).map_err(|e| {
+            let e = e.into();
+            if is_not_found(&e) {
+                e
+            } else {
+                e.context(format!("Failed to get metadata from <{}> store", S::KIND))
+                    .into()
+            }
+        })
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, Error> {
+        Store::list(self, prefix).map_err(|e| {
+            e.into()
+                .context(format!("Failed to list assets of <{}> store", S::KIND))
+                .into()
+        })
+    }
+}
+
+trait AnySink<I> {
+    fn write(&mut self, id: &I, data: &[u8]) -> Result<(), Error>;
+}
+
+impl<I, S> AnySink<I> for S
+where
+    S: AssetSink<I>,
+    S::Error: Into<Error>,
 {
-    fn fetch(&mut self, id: &I) -> Result<&mut Read, Error> {
-        let reader = self.0.fetch(id).map_err(|e| {
+    fn write(&mut self, id: &I, data: &[u8]) -> Result<(), Error> {
+        AssetSink::write(self, id, data).map_err(|e| {
             e.into()
-                .context(format!("Failed to fetch asset from <{}> store", S::KIND))
-        })?;
-        self.1 = Some(reader);
-        Ok(self.1.as_mut().unwrap())
+                .context(format!("Failed to write asset to <{}> store", S::KIND))
+                .into()
+        })
+    }
+}
+
+fn fetch_error<E>(err: E, kind: &str) -> Error
+where
+    E: Into<Error>,
+{
+    err.into()
+        .context(format!("Failed to fetch asset from <{}> store", kind))
+        .into()
+}
+
+/// Way loader wants to access asset data.
+#[derive(Clone, Copy, Debug)]
+enum Access {
+    Read,
+    Seek,
+    Bytes,
+}
+
+/// Asset data fetched from store.
+enum Data {
+    Read(Box<Read>),
+    Seek(Box<SeekRead>),
+    Bytes(Bytes),
+}
+
+impl Data {
+    fn into_read(self) -> Box<Read> {
+        match self {
+            Data::Read(reader) => reader,
+            Data::Seek(reader) => Box::new(reader),
+            Data::Bytes(bytes) => Box::new(Cursor::new(bytes)),
+        }
+    }
+
+    /// Get seekable reader buffering data if necessary.
+    fn into_seekable(self) -> Result<Box<SeekRead>, io::Error> {
+        match self {
+            Data::Read(reader) => Ok(Box::new(Cursor::new(buffer(reader)?))),
+            Data::Seek(reader) => Ok(reader),
+            Data::Bytes(bytes) => Ok(Box::new(Cursor::new(bytes))),
+        }
+    }
+
+    /// Get data in memory buffering it if necessary.
+    fn into_bytes(self) -> Result<Bytes, io::Error> {
+        match self {
+            Data::Read(reader) => buffer(reader).map(Bytes::from),
+            Data::Seek(reader) => buffer(reader).map(Bytes::from),
+            Data::Bytes(bytes) => Ok(bytes),
+        }
+    }
+
+    /// Verify data against hash.
+    #[cfg(feature = "content")]
+    fn verified(self, hash: ContentHash) -> Result<Data, Error> {
+        let bytes = self.into_bytes()?;
+        hash.verify(&bytes)?;
+        Ok(Data::Bytes(bytes))
+    }
+}
+
+fn buffer<R>(mut reader: R) -> Result<Vec<u8>, io::Error>
+where
+    R: Read,
+{
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+trait AnyFormat<I> {
+    fn request(&self, assets: &mut Assets<I>, id: &str) -> Result<Box<Any>, Error>;
+    fn name(&self, id: &I) -> Option<String>;
+}
+
+struct DefaultFormat<A, F> {
+    format: F,
+    marker: PhantomData<fn() -> A>,
+}
+
+impl<I, A, F> AnyFormat<I> for DefaultFormat<A, F>
+where
+    I: Debug + Hash + Eq + Clone + AsRef<Path> + for<'a> From<&'a str>,
+    A: Asset,
+    A::Loader: AssetLoader<A, F>,
+    <A::Loader as AssetLoader<A, F>>::Error: Into<Error>,
+    F: Clone,
+{
+    fn request(&self, assets: &mut Assets<I>, id: &str) -> Result<Box<Any>, Error> {
+        let handle = assets.load::<A, F>(I::from(id), self.format.clone())?;
+        Ok(Box::new(handle))
     }
 
-    fn close(&mut self) {
-        self.1 = None;
+    fn name(&self, id: &I) -> Option<String> {
+        id.as_ref().to_str().map(String::from)
     }
 }
 
+/// Options of the store registered in `Assets`.
+pub struct StoreOptions<I> {
+    priority: i32,
+    filters: Vec<Box<Fn(&I) -> bool + Send + Sync>>,
+}
+
+impl<I> Default for StoreOptions<I> {
+    fn default() -> Self {
+        StoreOptions {
+            priority: 0,
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl<I> StoreOptions<I> {
+    /// Create default options.
+    /// Store gets priority 0 and accepts all ids.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set store priority.
+    /// Stores with higher priority are tried first.
+    /// Stores with equal priority are tried in order they were added.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Add filter for ids.
+    /// Store is tried only for ids accepted by all filters.
+    pub fn with_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&I) -> bool + Send + Sync + 'static,
+    {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    fn accepts(&self, id: &I) -> bool {
+        self.filters.iter().all(|filter| filter(id))
+    }
+}
+
+impl<I> StoreOptions<I>
+where
+    I: AsRef<Path>,
+{
+    /// Accept only ids that start with prefix.
+    pub fn with_prefix(self, prefix: &str) -> Self {
+        let prefix = prefix.to_owned();
+        self.with_filter(move |id: &I| {
+            id.as_ref()
+                .to_str()
+                .map_or(false, |id| id.starts_with(&prefix))
+        })
+    }
+
+    /// Accept only ids with specified `scheme://` prefix.
+    pub fn with_scheme(self, scheme: &str) -> Self {
+        self.with_prefix(&format!("{}://", scheme))
+    }
+}
+
+struct StoreEntry<I> {
+    store: Box<AnyStore<I> + Send + Sync>,
+    options: StoreOptions<I>,
+}
+
 /// Manages loaders and caches assets.
 /// Should be able to load any asset type.
-pub struct Assets<I = PathBuf> {
-    stores: Vec<Box<AnyStore<I> + Send + Sync>>,
-    loaders: HashMap<TypeId, Box<Any + Send + Sync>>,
+pub struct Assets<I = AssetId> {
+    stores: Vec<StoreEntry<I>>,
+    sink: Option<Box<AnySink<I> + Send + Sync>>,
+    loaders: HashMap<TypeId, Option<Box<Any + Send + Sync>>>,
+    formats: HashMap<TypeId, Arc<AnyFormat<I> + Send + Sync>>,
     cache: HashMap<(I, TypeId), Box<Any + Send + Sync>>,
+    names: HashMap<(TypeId, HandleId), I>,
+    loading: HashSet<(I, TypeId)>,
+    redirects: Redirects<I>,
+    redirected: HashSet<I>,
+    #[cfg(feature = "content")]
+    verify: bool,
 }
 
 impl<I> Default for Assets<I>
@@ -50,15 +284,23 @@ where
     fn default() -> Self {
         Assets {
             stores: Default::default(),
+            sink: None,
             loaders: Default::default(),
+            formats: Default::default(),
             cache: Default::default(),
+            names: Default::default(),
+            loading: HashSet::new(),
+            redirects: Redirects::new(),
+            redirected: HashSet::new(),
+            #[cfg(feature = "content")]
+            verify: false,
         }
     }
 }
 
 impl<I> Assets<I>
 where
-    I: Debug + Hash + Eq,
+    I: Debug + Hash + Eq + Clone,
 {
     /// Create new `Assets`
     pub fn new() -> Self {
@@ -70,10 +312,42 @@ where
     where
         S: Store<I> + Send + Sync + 'static,
         S::Error: Into<Error>,
-        S::Reader: Send + Sync + 'static,
+        S::Reader: 'static,
+    {
+        self.add_store_with_options(store, StoreOptions::new());
+    }
+
+    /// Add store to the manager with options.
+    pub fn add_store_with_options<S>(&mut self, store: S, options: StoreOptions<I>)
+    where
+        S: Store<I> + Send + Sync + 'static,
+        S::Error: Into<Error>,
+        S::Reader: 'static,
     {
-        self.stores.push(Box::new((store, None)));
-        info!("Store {} registered", S::KIND);
+        let index = self
+            .stores
+            .iter()
+            .position(|entry| entry.options.priority < options.priority)
+            .unwrap_or(self.stores.len());
+        info!("Store {} registered with priority {}", S::KIND, options.priority);
+        self.stores.insert(
+            index,
+            StoreEntry {
+                store: Box::new(store),
+                options,
+            },
+        );
+    }
+
+    /// Add store to the manager with options.
+    pub fn with_store_with_options<S>(mut self, store: S, options: StoreOptions<I>) -> Self
+    where
+        S: Store<I> + Send + Sync + 'static,
+        S::Error: Into<Error>,
+        S::Reader: 'static,
+    {
+        self.add_store_with_options(store, options);
+        self
     }
 
     /// Add store to the manager.
@@ -81,18 +355,40 @@ where
     where
         S: Store<I> + Send + Sync + 'static,
         S::Error: Into<Error>,
-        S::Reader: Send + Sync + 'static,
+        S::Reader: 'static,
     {
         self.add_store(store);
         self
     }
 
+    /// Set store to save assets into.
+    pub fn set_sink<S>(&mut self, sink: S)
+    where
+        S: AssetSink<I> + Send + Sync + 'static,
+        S::Error: Into<Error>,
+    {
+        self.sink = Some(Box::new(sink));
+        info!("Sink {} registered", S::KIND);
+    }
+
+    /// Set store to save assets into.
+    pub fn with_sink<S>(mut self, sink: S) -> Self
+    where
+        S: AssetSink<I> + Send + Sync + 'static,
+        S::Error: Into<Error>,
+    {
+        self.set_sink(sink);
+        self
+    }
+
     /// Register asset loader.
+    /// Loader is busy while it loads or saves an asset,
+    /// so assets referenced by that asset must be loaded by other loaders.
     pub fn add_loader<L>(&mut self, loader: L)
     where
         L: AssetLoaderKind + Any + Send + Sync,
     {
-        self.loaders.insert(TypeId::of::<L>(), Box::new(loader));
+        self.loaders.insert(TypeId::of::<L>(), Some(Box::new(loader)));
         info!("Loader {} registered", L::KIND);
     }
 
@@ -105,6 +401,36 @@ where
         self
     }
 
+    /// Set table of redirects consulted before looking up assets.
+    /// Assets loaded by old ids are cached and named by their new ids.
+    pub fn set_redirects(&mut self, redirects: Redirects<I>) {
+        info!("{} redirects registered", redirects.len());
+        redirects.report();
+        self.redirects = redirects;
+        self.redirected.clear();
+    }
+
+    /// Set table of redirects consulted before looking up assets.
+    pub fn with_redirects(mut self, redirects: Redirects<I>) -> Self {
+        self.set_redirects(redirects);
+        self
+    }
+
+    /// Enable or disable verification of fetched data
+    /// against content hashes reported by stores.
+    #[cfg(feature = "content")]
+    pub fn set_verify(&mut self, verify: bool) {
+        self.verify = verify;
+    }
+
+    /// Enable or disable verification of fetched data
+    /// against content hashes reported by stores.
+    #[cfg(feature = "content")]
+    pub fn with_verify(mut self, verify: bool) -> Self {
+        self.set_verify(verify);
+        self
+    }
+
     /// Load asset from managed store.
     /// Or get cached asset.
     pub fn load_with<A, F>(
@@ -112,101 +438,401 @@ where
         id: I,
         format: F,
         loader: &mut A::Loader,
-    ) -> Result<Arc<A>, Error>
+    ) -> Result<Handle<A>, Error>
     where
         A: Asset + 'static,
         A::Loader: AssetLoader<A, F>,
         <A::Loader as AssetLoader<A, F>>::Error: Into<Error>,
     {
-        use failure::err_msg;
-        use std::collections::hash_map::Entry;
-
-        let id = id.into();
+        let id = self.redirect(id)?;
 
         debug!("Load asset {:?} of kind {}", id, A::KIND);
-        match self.cache.entry((id, TypeId::of::<A>())) {
-            Entry::Vacant(vacant) => {
-                let mut errors = Vec::new();
-                for store in &mut self.stores {
-                    match store.fetch(&vacant.key().0) {
-                        Ok(reader) => {
-                            let asset = loader.load(format, reader).map_err(|e| {
-                                e.into()
-                                    .context(format!("Failed to load asset <{}>", A::KIND))
-                            })?;
-                            let asset = Arc::new(asset);
-                            vacant.insert(Box::new(asset.clone()));
-                            return Ok(asset);
-                        }
-                        Err(err) => {
-                            errors.push(err);
-                        }
-                    }
-                }
-
-                Err(errors.into_iter().fold(
-                    err_msg(format!("Failed to find asset <{}>", A::KIND)),
-                    |a, e| e.context(a).into(),
-                ))
-            }
-            Entry::Occupied(occupied) => {
-                let asset: &Arc<A> = Any::downcast_ref::<Arc<A>>(&**occupied.get())
-                    .expect("Cached assets are mapped by `TypeId`");
-                Ok(Arc::clone(asset))
-            }
+        if let Some(handle) = self.cached::<A>(&id) {
+            return Ok(handle);
         }
+
+        let reader = self.fetch(&id, A::KIND, Access::Read)?.into_read();
+        let asset = with_context(self, || loader.load(format, reader)).map_err(|e| {
+            e.into()
+                .context(format!("Failed to load asset <{}>", A::KIND))
+        })?;
+        Ok(self.insert(id, asset))
     }
 
     /// Load asset from managed store.
     /// Or get cached asset.
-    pub fn load<A, F>(&mut self, id: I, format: F) -> Result<Arc<A>, Error>
+    pub fn load<A, F>(&mut self, id: I, format: F) -> Result<Handle<A>, Error>
     where
         A: Asset + 'static,
         A::Loader: AssetLoader<A, F>,
         <A::Loader as AssetLoader<A, F>>::Error: Into<Error>,
     {
+        self.load_data(id, Access::Read, |loader: &mut A::Loader, data| {
+            loader.load(format, data.into_read()).map_err(Into::into)
+        })
+    }
+
+    /// Load asset with random access to its data from managed store.
+    /// Or get cached asset.
+    /// Data is buffered in memory if store can't provide seekable reader.
+    pub fn load_seekable<A, F>(&mut self, id: I, format: F) -> Result<Handle<A>, Error>
+    where
+        A: Asset + 'static,
+        A::Loader: SeekAssetLoader<A, F>,
+        <A::Loader as AssetLoader<A, F>>::Error: Into<Error>,
+    {
+        self.load_data(id, Access::Seek, |loader: &mut A::Loader, data| {
+            let reader = data.into_seekable()?;
+            loader.load_seekable(format, reader).map_err(Into::into)
+        })
+    }
+
+    /// Load asset from its data in memory from managed store.
+    /// Or get cached asset.
+    /// Data is buffered in memory if store can't provide it without copying.
+    pub fn load_bytes<A, F>(&mut self, id: I, format: F) -> Result<Handle<A>, Error>
+    where
+        A: Asset + 'static,
+        A::Loader: BytesAssetLoader<A, F>,
+        <A::Loader as AssetLoader<A, F>>::Error: Into<Error>,
+    {
+        self.load_data(id, Access::Bytes, |loader: &mut A::Loader, data| {
+            let bytes = data.into_bytes()?;
+            loader.load_bytes(format, bytes).map_err(Into::into)
+        })
+    }
+
+    fn load_data<A, G>(&mut self, id: I, access: Access, load: G) -> Result<Handle<A>, Error>
+    where
+        A: Asset + 'static,
+        G: FnOnce(&mut A::Loader, Data) -> Result<A, Error>,
+    {
+        use failure::err_msg;
+
+        let id = self.redirect(id)?;
+
+        debug!("Load asset {:?} of kind {}", id, A::KIND);
+        if let Some(handle) = self.cached::<A>(&id) {
+            return Ok(handle);
+        }
+
+        // Asset is cached only after it is loaded,
+        // so assets that reference each other are detected by ids being loaded.
+        let key = (id.clone(), TypeId::of::<A>());
+        if !self.loading.insert(key.clone()) {
+            return Err(err_msg(format!(
+                "Asset {:?} of kind <{}> references itself",
+                id,
+                A::KIND
+            )));
+        }
+
+        let result = self.fetch(&id, A::KIND, access).and_then(|data| {
+            self.use_loader::<A, _, _>(|loader| load(loader, data))?
+                .map_err(|e| e.context(format!("Failed to load asset <{}>", A::KIND)).into())
+        });
+        self.loading.remove(&key);
+        Ok(self.insert(id, result?))
+    }
+
+    /// Save asset into the sink with loader registered for the asset type.
+    /// Cached asset with the same id is dropped, so next load reads saved data.
+    pub fn save<A, F>(&mut self, id: I, asset: &A, format: F) -> Result<(), Error>
+    where
+        A: Asset + 'static,
+        A::Loader: AssetSaver<A, F>,
+        <A::Loader as AssetSaver<A, F>>::Error: Into<Error>,
+    {
+        use failure::err_msg;
+
+        let id = self.redirect(id)?;
+
+        debug!("Save asset {:?} of kind {}", id, A::KIND);
+        if self.sink.is_none() {
+            return Err(err_msg("No sink is registered to save assets into"));
+        }
+
+        let mut data = Vec::new();
+        let result = self.use_loader::<A, _, _>(|loader| loader.save(format, asset, &mut data))?;
+        result.map_err(|e| {
+            e.into()
+                .context(format!("Failed to save asset <{}>", A::KIND))
+        })?;
+
+        self.sink
+            .as_mut()
+            .expect("Sink presence is checked")
+            .write(&id, &data)?;
+        if let Some(cached) = self.cache.remove(&(id, TypeId::of::<A>())) {
+            let handle = Any::downcast_ref::<Handle<A>>(&*cached)
+                .expect("Cached assets are mapped by `TypeId`");
+            self.names.remove(&(TypeId::of::<A>(), handle.id()));
+        }
+        Ok(())
+    }
+
+    /// Replace id with the target of its redirects.
+    /// Use of each redirected id is logged once.
+    fn redirect(&mut self, id: I) -> Result<I, Error> {
+        let target = match self.redirects.resolve(&id)? {
+            Some(target) => target.clone(),
+            None => return Ok(id),
+        };
+        if !self.redirected.contains(&id) {
+            warn!("Asset {:?} is redirected to {:?}", id, target);
+            self.redirected.insert(id);
+        }
+        Ok(target)
+    }
+
+    /// Run closure with loader of the asset type and `self` as handle context.
+    fn use_loader<A, G, R>(&mut self, f: G) -> Result<R, Error>
+    where
+        A: Asset + 'static,
+        G: FnOnce(&mut A::Loader) -> R,
+    {
+        use failure::err_msg;
+
+        let kind = TypeId::of::<A::Loader>();
+
+        // Loader is taken out for the time of loading
+        // so that assets referenced by the one being loaded could be requested from `self`.
+        let mut loader = self
+            .loaders
+            .get_mut(&kind)
+            .ok_or_else(|| err_msg(format!("Loader for <{}> is not registered", A::KIND)))?
+            .take()
+            .ok_or_else(|| {
+                err_msg(format!(
+                    "Loader for <{}> is busy loading asset that references this one",
+                    A::KIND
+                ))
+            })?;
+
+        let result = {
+            let loader = Any::downcast_mut::<A::Loader>(&mut *loader)
+                .expect("Loaders are mapped by `TypeId`");
+            with_context(self, || f(loader))
+        };
+        self.loaders.insert(kind, Some(loader));
+        Ok(result)
+    }
+
+    fn cached<A>(&self, id: &I) -> Option<Handle<A>>
+    where
+        A: Asset,
+    {
+        self.cache
+            .get(&(id.clone(), TypeId::of::<A>()))
+            .map(|cached| {
+                Any::downcast_ref::<Handle<A>>(&**cached)
+                    .expect("Cached assets are mapped by `TypeId`")
+                    .clone()
+            })
+    }
+
+    fn insert<A>(&mut self, id: I, asset: A) -> Handle<A>
+    where
+        A: Asset,
+    {
+        let handle = Handle::new(asset);
+        self.names
+            .insert((TypeId::of::<A>(), handle.id()), id.clone());
+        self.cache
+            .insert((id, TypeId::of::<A>()), Box::new(handle.clone()));
+        handle
+    }
+
+    /// Get asset metadata from the first store that has the asset.
+    /// Returns `None` if that store doesn't provide metadata.
+    /// Presence of the asset in such store is checked by fetching it.
+    pub fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, Error> {
         use failure::err_msg;
-        use std::collections::hash_map::Entry;
-
-        let id = id.into();
-
-        match self.cache.entry((id, TypeId::of::<A>())) {
-            Entry::Vacant(vacant) => {
-                let loader = self
-                    .loaders
-                    .get_mut(&TypeId::of::<A::Loader>())
-                    .ok_or_else(|| err_msg(format!("Loader for <{}> is not registered", A::KIND)))?;
-                let loader = Any::downcast_mut::<A::Loader>(&mut **loader)
-                    .expect("Loaders are mapped by `TypeId`");
-
-                let mut errors = Vec::new();
-                for store in &mut self.stores {
-                    match store.fetch(&vacant.key().0) {
-                        Ok(reader) => {
-                            let asset: A = loader.load(format, reader).map_err(|e| {
-                                e.into()
-                                    .context(format!("Failed to load asset <{}>", A::KIND))
-                            })?;
-                            let asset = Arc::new(asset);
-                            vacant.insert(Box::new(Arc::clone(&asset)));
-                            return Ok(asset);
-                        }
-                        Err(err) => {
-                            errors.push(err);
-                        }
+
+        let id = &self.redirect(id.clone())?;
+        for entry in &mut self.stores {
+            if !entry.options.accepts(id) {
+                continue;
+            }
+            let result = match entry.store.metadata(id) {
+                Ok(Some(metadata)) => return Ok(Some(metadata)),
+                Ok(None) => entry.store.fetch(id).map(|_| None),
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(metadata) => return Ok(metadata),
+                Err(err) => {
+                    if !is_not_found(&err) {
+                        return Err(err);
                     }
                 }
+            }
+        }
 
-                Err(errors.into_iter().fold(
-                    err_msg(format!("Failed to find asset <{}>", A::KIND)),
-                    |a, e| e.context(a).into(),
-                ))
+        Err(err_msg(format!("No store has asset {:?}", id)))
+    }
+
+    fn fetch(&mut self, id: &I, kind: &str, access: Access) -> Result<Data, Error> {
+        use failure::err_msg;
+
+        let mut errors = Vec::new();
+        for entry in &mut self.stores {
+            if !entry.options.accepts(id) {
+                continue;
             }
-            Entry::Occupied(occupied) => {
-                let asset: &Arc<A> = Any::downcast_ref::<Arc<A>>(&**occupied.get())
-                    .expect("Cached assets are mapped by `TypeId`");
-                Ok(Arc::clone(asset))
+            let store = &mut entry.store;
+            let result = match access {
+                Access::Read => store.fetch(id).map(Data::Read),
+                Access::Seek => store.fetch_seekable(id).and_then(|reader| match reader {
+                    Some(reader) => Ok(Data::Seek(reader)),
+                    None => store.fetch(id).map(Data::Read),
+                }),
+                Access::Bytes => store.fetch_bytes(id).and_then(|bytes| match bytes {
+                    Some(bytes) => Ok(Data::Bytes(bytes)),
+                    None => store.fetch(id).map(Data::Read),
+                }),
+            };
+
+            #[cfg(feature = "content")]
+            let result = match result {
+                Ok(data) if self.verify => match store.metadata(id) {
+                    Ok(Some(ref metadata)) if metadata.hash().is_some() => {
+                        data.verified(metadata.hash().unwrap()).map_err(|e| {
+                            e.context(format!("Asset {:?} failed verification", id))
+                                .into()
+                        })
+                    }
+                    Ok(_) => Ok(data),
+                    Err(err) => Err(err),
+                },
+                result => result,
+            };
+
+            match result {
+                Ok(data) => return Ok(data),
+                Err(err) => {
+                    errors.push(err);
+                }
             }
         }
+
+        if errors.is_empty() {
+            return Err(err_msg(format!(
+                "No store can provide asset <{}> {:?}",
+                kind, id
+            )));
+        }
+
+        // Every store's error is described in the message,
+        // while the last one is kept as the cause for downcasting.
+        let causes: Vec<String> = errors
+            .iter()
+            .map(|err| {
+                err.iter_chain()
+                    .map(|cause| cause.to_string())
+                    .collect::<Vec<_>>()
+                    .join(": ")
+            })
+            .collect();
+        let last = errors.pop().unwrap();
+        Err(last
+            .context(format!(
+                "Failed to find asset <{}> {:?}: {}",
+                kind,
+                id,
+                causes.join("; ")
+            ))
+            .into())
+    }
+}
+
+impl<I> Assets<I>
+where
+    I: Debug + Hash + Eq + Clone + AsRef<Path> + for<'a> From<&'a str> + 'static,
+{
+    /// List ids of assets in `prefix` directory of all stores.
+    /// Ids rejected by store filters are skipped.
+    pub fn list(&mut self, prefix: &str) -> Result<Vec<String>, Error> {
+        let mut ids = BTreeSet::new();
+        for entry in &mut self.stores {
+            for id in entry.store.list(prefix)? {
+                if entry.options.accepts(&I::from(&id)) {
+                    ids.insert(id);
+                }
+            }
+        }
+        Ok(ids.into_iter().collect())
+    }
+
+    /// Load all assets with ids matching glob pattern.
+    /// See `Glob` for pattern syntax.
+    pub fn load_glob<A, F>(&mut self, pattern: &str, format: F) -> Result<Vec<Handle<A>>, Error>
+    where
+        A: Asset + 'static,
+        A::Loader: AssetLoader<A, F>,
+        <A::Loader as AssetLoader<A, F>>::Error: Into<Error>,
+        F: Clone,
+    {
+        let glob = Glob::new(pattern);
+        let ids = self.list(glob.dir())?;
+        ids.iter()
+            .filter(|id| glob.matches(id))
+            .map(|id| self.load(I::from(id), format.clone()))
+            .collect()
+    }
+
+    /// Register format to load assets of type `A` with
+    /// when they are requested by id during handle deserialization.
+    /// Handles to assets of type `A` can be serialized only if format is registered.
+    pub fn add_format<A, F>(&mut self, format: F)
+    where
+        A: Asset,
+        A::Loader: AssetLoader<A, F>,
+        <A::Loader as AssetLoader<A, F>>::Error: Into<Error>,
+        F: Clone + Send + Sync + 'static,
+    {
+        self.formats.insert(
+            TypeId::of::<A>(),
+            Arc::new(DefaultFormat {
+                format,
+                marker: PhantomData::<fn() -> A>,
+            }),
+        );
+        info!("Default format for {} registered", A::KIND);
+    }
+
+    /// Register format to load assets of type `A` with
+    /// when they are requested by id during handle deserialization.
+    pub fn with_format<A, F>(mut self, format: F) -> Self
+    where
+        A: Asset,
+        A::Loader: AssetLoader<A, F>,
+        <A::Loader as AssetLoader<A, F>>::Error: Into<Error>,
+        F: Clone + Send + Sync + 'static,
+    {
+        self.add_format::<A, F>(format);
+        self
+    }
+}
+
+impl<I> HandleContext for Assets<I>
+where
+    I: Debug + Hash + Eq + Clone,
+{
+    fn asset_id(&self, kind: TypeId, handle: HandleId) -> Option<String> {
+        let format = self.formats.get(&kind)?;
+        let id = self.names.get(&(kind, handle))?;
+        format.name(id)
+    }
+
+    fn request(&mut self, kind: TypeId, id: &str) -> Result<Box<Any>, Error> {
+        use failure::err_msg;
+
+        let format = self
+            .formats
+            .get(&kind)
+            .cloned()
+            .ok_or_else(|| err_msg(format!("Format for asset {:?} is not registered", id)))?;
+        format.request(self, id)
     }
 }
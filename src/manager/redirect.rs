@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use failure::{err_msg, Error};
+
+#[cfg(all(feature = "ron", feature = "serde"))]
+use ron;
+
+#[cfg(all(feature = "ron", feature = "serde"))]
+use serde::de::DeserializeOwned;
+
+#[cfg(all(feature = "ron", feature = "serde"))]
+use std::io::Read;
+
+use id::AssetId;
+
+/// Table mapping old asset ids to new ones.
+/// Lets assets be renamed and moved while data referencing old ids keeps working.
+#[derive(Clone, Debug)]
+pub struct Redirects<I = AssetId> {
+    map: HashMap<I, I>,
+}
+
+impl<I> Default for Redirects<I>
+where
+    I: Hash + Eq,
+{
+    fn default() -> Self {
+        Redirects {
+            map: HashMap::new(),
+        }
+    }
+}
+
+impl<I> Redirects<I>
+where
+    I: Hash + Eq,
+{
+    /// Create empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redirect id to another one.
+    /// Returns previous target if any.
+    pub fn insert(&mut self, from: I, to: I) -> Option<I> {
+        self.map.insert(from, to)
+    }
+
+    /// Redirect id to another one.
+    pub fn with(mut self, from: I, to: I) -> Self {
+        self.insert(from, to);
+        self
+    }
+
+    /// Get direct target of the id.
+    pub fn get(&self, id: &I) -> Option<&I> {
+        self.map.get(id)
+    }
+
+    /// Get number of redirects.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Check if there are no redirects.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Follow redirects of the id to the end of the chain.
+    /// Returns `None` if id is not redirected.
+    /// Fails if redirects form a cycle.
+    pub fn resolve(&self, id: &I) -> Result<Option<&I>, Error>
+    where
+        I: Debug,
+    {
+        let mut chain: Vec<&I> = Vec::new();
+        let mut current = id;
+        while let Some(next) = self.map.get(current) {
+            if next == id || chain.contains(&next) {
+                return Err(err_msg(format!("Redirects of asset {:?} form a cycle", id)));
+            }
+            chain.push(next);
+            current = next;
+        }
+        Ok(chain.last().cloned())
+    }
+
+    /// Log warnings about chains and cycles of redirects.
+    pub(crate) fn report(&self)
+    where
+        I: Debug,
+    {
+        for (from, to) in &self.map {
+            match self.resolve(from) {
+                Err(err) => warn!("{}", err),
+                Ok(Some(target)) if target != to => warn!(
+                    "Asset {:?} is redirected through {:?} to {:?}. Redirect it to the last one directly",
+                    from, to, target
+                ),
+                Ok(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "ron", feature = "serde"))]
+impl<I> Redirects<I>
+where
+    I: Hash + Eq + DeserializeOwned,
+{
+    /// Read table from RON map of old ids to new ones.
+    pub fn from_reader<R>(reader: R) -> Result<Self, ron::de::Error>
+    where
+        R: Read,
+    {
+        ron::de::from_reader(reader).map(|map| Redirects { map })
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt;
+use std::path::{Component, Path};
+
+use id::AssetId;
+
+/// Case policy for virtual paths.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Case {
+    /// Path case is preserved.
+    Sensitive,
+
+    /// Path is converted to lower case.
+    Insensitive,
+}
+
+impl Default for Case {
+    fn default() -> Self {
+        Case::Sensitive
+    }
+}
+
+/// Canonical virtual path to an asset.
+///
+/// Virtual path consists of optional `scheme://` prefix followed by
+/// `/` separated components without `.` and `..`.
+/// Leading `..` components that would escape root are dropped.
+/// Scheme is always lower case.
+///
+/// `"textures/../a.png"`, `"./a.png"`, `"/a.png"` and `"a.png"` are all normalized into `"a.png"`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VirtualPath {
+    path: String,
+    scheme: usize,
+}
+
+impl VirtualPath {
+    /// Normalize path preserving case.
+    pub fn new(path: &str) -> Self {
+        Self::with_case(path, Case::Sensitive)
+    }
+
+    /// Normalize path with specified case policy.
+    pub fn with_case(path: &str, case: Case) -> Self {
+        let (scheme, rest) = match path.find("://") {
+            Some(index) if is_scheme(&path[..index]) => (Some(&path[..index]), &path[index + 3..]),
+            _ => (None, path),
+        };
+
+        let mut components: Vec<&str> = Vec::new();
+        for component in rest.split(|c| c == '/' || c == '\\') {
+            match component {
+                "" | "." => {}
+                ".." => {
+                    components.pop();
+                }
+                component => components.push(component),
+            }
+        }
+
+        let mut normalized = String::with_capacity(path.len());
+        if let Some(scheme) = scheme {
+            normalized.push_str(&scheme.to_lowercase());
+            normalized.push_str("://");
+        }
+        let scheme = normalized.len();
+
+        for (index, component) in components.into_iter().enumerate() {
+            if index > 0 {
+                normalized.push('/');
+            }
+            match case {
+                Case::Sensitive => normalized.push_str(component),
+                Case::Insensitive => normalized.push_str(&component.to_lowercase()),
+            }
+        }
+
+        VirtualPath {
+            path: normalized,
+            scheme,
+        }
+    }
+
+    /// Normalize filesystem path preserving case.
+    /// Non-unicode characters are replaced.
+    pub fn from_path(path: &Path) -> Self {
+        let mut normalized = String::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(component) => {
+                    normalized.push('/');
+                    normalized.push_str(&component.to_string_lossy());
+                }
+                Component::ParentDir => normalized.push_str("/.."),
+                Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+            }
+        }
+        Self::new(&normalized)
+    }
+
+    /// Get scheme of the path if any.
+    pub fn scheme(&self) -> Option<&str> {
+        if self.scheme > 0 {
+            Some(&self.path[..self.scheme - 3])
+        } else {
+            None
+        }
+    }
+
+    /// Get path without scheme.
+    pub fn path(&self) -> &str {
+        &self.path[self.scheme..]
+    }
+
+    /// Get whole path as string.
+    pub fn as_str(&self) -> &str {
+        &self.path
+    }
+
+    /// Get interned id for the path.
+    pub fn id(&self) -> AssetId {
+        AssetId::intern(&self.path)
+    }
+}
+
+impl fmt::Display for VirtualPath {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.path)
+    }
+}
+
+impl AsRef<str> for VirtualPath {
+    fn as_ref(&self) -> &str {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for VirtualPath {
+    fn as_ref(&self) -> &Path {
+        Path::new(&self.path)
+    }
+}
+
+impl<'a> From<&'a str> for VirtualPath {
+    fn from(path: &'a str) -> Self {
+        VirtualPath::new(path)
+    }
+}
+
+impl<'a> From<&'a Path> for VirtualPath {
+    fn from(path: &'a Path) -> Self {
+        VirtualPath::from_path(path)
+    }
+}
+
+impl From<VirtualPath> for AssetId {
+    fn from(path: VirtualPath) -> Self {
+        path.id()
+    }
+}
+
+/// Glob pattern matching virtual paths.
+///
+/// `*` matches any characters except `/`,
+/// `**` matches any characters including `/`,
+/// `?` matches single character except `/`.
+/// `**/` also matches no directories, so `"a/**/b"` matches `"a/b"`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Glob {
+    pattern: Vec<char>,
+    dir: String,
+}
+
+impl Glob {
+    /// Create glob from pattern. Pattern is normalized as virtual path.
+    pub fn new(pattern: &str) -> Self {
+        let pattern = VirtualPath::new(pattern);
+        let literal = pattern
+            .as_str()
+            .find(|c| c == '*' || c == '?')
+            .map_or(pattern.as_str(), |index| &pattern.as_str()[..index]);
+        let dir = match literal.rfind('/') {
+            Some(index) => literal[..index].to_owned(),
+            None => String::new(),
+        };
+
+        Glob {
+            pattern: pattern.as_str().chars().collect(),
+            dir,
+        }
+    }
+
+    /// Get deepest directory that contains all matching paths.
+    pub fn dir(&self) -> &str {
+        &self.dir
+    }
+
+    /// Check if normalized path matches the pattern.
+    pub fn matches(&self, path: &str) -> bool {
+        let path: Vec<char> = path.chars().collect();
+        glob(&self.pattern, &path)
+    }
+}
+
+/// Match path against pattern in `O(pattern * path)` time.
+/// `matched[i][j]` tells whether `pattern[i..]` matches `path[j..]`.
+fn glob(pattern: &[char], path: &[char]) -> bool {
+    let width = path.len() + 1;
+    let mut matched = vec![false; (pattern.len() + 1) * width];
+    matched[pattern.len() * width + path.len()] = true;
+
+    for i in (0..pattern.len()).rev() {
+        // Whether rest of the pattern after `**` matches any suffix of `path[j..]`.
+        let mut any_suffix = false;
+        for j in (0..width).rev() {
+            let next = path.get(j);
+            matched[i * width + j] = match pattern[i] {
+                '*' if pattern.get(i + 1) == Some(&'*') => {
+                    any_suffix = any_suffix || matched[(i + 2) * width + j];
+                    // `**/` may match no directories at all.
+                    any_suffix || (pattern.get(i + 2) == Some(&'/') && matched[(i + 3) * width + j])
+                }
+                '*' => {
+                    matched[(i + 1) * width + j]
+                        || (next.is_some_and(|&c| c != '/') && matched[i * width + j + 1])
+                }
+                '?' => next.is_some_and(|&c| c != '/') && matched[(i + 1) * width + j + 1],
+                c => next == Some(&c) && matched[(i + 1) * width + j + 1],
+            };
+        }
+    }
+
+    matched[0]
+}
+
+fn is_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+}
@@ -70,6 +70,11 @@ where
     pub fn get(&self, id: &I) -> Option<Range<u32>> {
         self.animations.get(id).cloned()
     }
+
+    /// Get reference to frames range.
+    pub fn animation(&self, id: &I) -> Option<&Range<u32>> {
+        self.animations.get(id)
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -1,11 +1,11 @@
 use futures::io::{AllowStdIo, AsyncRead};
 
-use store::Store;
+use store::{Metadata, Store};
 
 /// Most stores are asynchronous in nature.
 /// Therefore synchronous access to assets data is internally synchronized.
 /// `futures` feature allows to work with asynchronous stores more naturally.
-pub trait AsyncStore<I>: Store<I> {
+pub trait AsyncStore<I: ?Sized>: Store<I> {
     /// Stream of raw data chunks.
     type AsyncReader: AsyncRead;
 
@@ -32,10 +32,15 @@ where
     fn fetch(&mut self, id: &I) -> Result<S::Reader, S::Error> {
         self.store.fetch(id)
     }
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, S::Error> {
+        self.store.metadata(id)
+    }
 }
 
 impl<I, S> AsyncStore<I> for AsyncStoreWrapper<S>
 where
+    I: ?Sized,
     S: Store<I>,
 {
     type AsyncReader = AllowStdIo<S::Reader>;
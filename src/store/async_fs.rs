@@ -0,0 +1,253 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use futures::io::AsyncRead;
+use futures::task::{Context, Waker};
+use futures::{Async, Poll};
+
+use store::{AsyncStore, FsStore, Metadata, Store};
+
+/// Size of chunks files are read by.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Number of chunks that can be read ahead of the consumer.
+/// Reading is suspended when that many chunks are waiting
+/// and resumed when consumer takes one of them.
+const READ_AHEAD: usize = 4;
+
+/// `AsyncFsStore` reads files on a pool of blocking threads
+/// and yields data to asynchronous readers chunk by chunk.
+/// Executor threads never block on filesystem
+/// and pool threads never wait for slow readers.
+#[derive(Debug)]
+pub struct AsyncFsStore {
+    store: Arc<FsStore>,
+    jobs: Mutex<Sender<Job>>,
+}
+
+impl AsyncFsStore {
+    /// Create store which reads files found by `FsStore` on `threads` blocking threads.
+    pub fn new(store: FsStore, threads: usize) -> Self {
+        assert!(threads > 0, "At least one thread is required");
+
+        let (sender, receiver) = channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0..threads {
+            let receiver = Arc::clone(&receiver);
+            thread::Builder::new()
+                .name(format!("asset-fs-{}", index))
+                .spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job.run(),
+                        Err(_) => break,
+                    }
+                })
+                .expect("Failed to spawn blocking thread");
+        }
+
+        AsyncFsStore {
+            store: Arc::new(store),
+            jobs: Mutex::new(sender),
+        }
+    }
+
+    /// Start reading file on the pool.
+    /// Errors, including absence of the file, are yielded by the reader.
+    pub fn read<P>(&self, path: P) -> AsyncFsReader
+    where
+        P: AsRef<Path>,
+    {
+        let shared = Arc::new(Mutex::new(Shared {
+            chunks: VecDeque::new(),
+            done: false,
+            closed: false,
+            suspended: None,
+            waker: None,
+        }));
+        let job = Job {
+            store: Arc::clone(&self.store),
+            path: path.as_ref().to_path_buf(),
+            file: None,
+            shared: Arc::clone(&shared),
+        };
+        let jobs = self.jobs.lock().unwrap().clone();
+        jobs.send(job)
+            .expect("Blocking threads live as long as the store");
+
+        AsyncFsReader {
+            shared,
+            jobs,
+            chunk: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl<P> Store<P> for AsyncFsStore
+where
+    P: AsRef<Path> + ?Sized,
+{
+    type Error = io::Error;
+    type Reader = File;
+
+    const KIND: &'static str = "AsyncFilesystem";
+
+    fn fetch(&mut self, id: &P) -> Result<File, io::Error> {
+        self.store.find(id)
+    }
+
+    fn metadata(&mut self, id: &P) -> Result<Option<Metadata>, io::Error> {
+        self.store.find_metadata(id).map(Some)
+    }
+}
+
+impl<P> AsyncStore<P> for AsyncFsStore
+where
+    P: AsRef<Path> + ?Sized,
+{
+    type AsyncReader = AsyncFsReader;
+
+    fn fetch_async(&mut self, id: &P) -> Result<AsyncFsReader, io::Error> {
+        Ok(self.read(id))
+    }
+}
+
+/// State shared by reading job and its reader.
+struct Shared {
+    chunks: VecDeque<Result<Vec<u8>, io::Error>>,
+    done: bool,
+    closed: bool,
+    suspended: Option<Job>,
+    waker: Option<Waker>,
+}
+
+impl Shared {
+    fn push(&mut self, chunk: Result<Vec<u8>, io::Error>) {
+        self.chunks.push_back(chunk);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn finish(&mut self) {
+        self.done = true;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Reading of a file on blocking thread.
+/// Job returns its thread to the pool when reader falls behind
+/// and is sent to the pool again by the reader.
+struct Job {
+    store: Arc<FsStore>,
+    path: PathBuf,
+    file: Option<File>,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Job {
+    fn run(mut self) {
+        let mut file = match self.file.take() {
+            Some(file) => file,
+            None => match self.store.find(&self.path) {
+                Ok(file) => file,
+                Err(err) => {
+                    let mut shared = self.shared.lock().unwrap();
+                    shared.push(Err(err));
+                    shared.finish();
+                    return;
+                }
+            },
+        };
+
+        loop {
+            let mut chunk = vec![0; CHUNK_SIZE];
+            let chunk = match file.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => {
+                    chunk.truncate(read);
+                    Ok(chunk)
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => Err(err),
+            };
+
+            let shared = Arc::clone(&self.shared);
+            let mut shared = shared.lock().unwrap();
+            if shared.closed {
+                // Reader is dropped.
+                return;
+            }
+            let failed = chunk.is_err();
+            shared.push(chunk);
+            if failed {
+                break;
+            }
+            if shared.chunks.len() >= READ_AHEAD {
+                self.file = Some(file);
+                shared.suspended = Some(self);
+                return;
+            }
+        }
+
+        // Reader sees end of data after all chunks are taken.
+        self.shared.lock().unwrap().finish();
+    }
+}
+
+/// Asynchronous reader of file data produced by `AsyncFsStore`.
+pub struct AsyncFsReader {
+    shared: Arc<Mutex<Shared>>,
+    jobs: Sender<Job>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+impl AsyncRead for AsyncFsReader {
+    fn poll_read(&mut self, cx: &mut Context, buf: &mut [u8]) -> Poll<usize, io::Error> {
+        if self.position == self.chunk.len() {
+            let (chunk, resumed) = {
+                let mut shared = self.shared.lock().unwrap();
+                match shared.chunks.pop_front() {
+                    Some(chunk) => (chunk, shared.suspended.take()),
+                    None if shared.done => return Ok(Async::Ready(0)),
+                    None => {
+                        // Chunks are pushed under the same lock,
+                        // so waker is registered before the next one arrives.
+                        shared.waker = Some(cx.waker().clone());
+                        return Ok(Async::Pending);
+                    }
+                }
+            };
+
+            if let Some(job) = resumed {
+                // Store is dropped if sending fails, suspended reading is abandoned then.
+                let _ = self.jobs.send(job);
+            }
+            self.chunk = chunk?;
+            self.position = 0;
+        }
+
+        let read = buf.len().min(self.chunk.len() - self.position);
+        buf[..read].copy_from_slice(&self.chunk[self.position..self.position + read]);
+        self.position += read;
+        Ok(Async::Ready(read))
+    }
+}
+
+impl Drop for AsyncFsReader {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.closed = true;
+        // Suspended job references shared state and must not outlive the reader.
+        shared.suspended = None;
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+#[cfg(feature = "memmap")]
+use memmap::Mmap;
+
+#[cfg(feature = "memmap")]
+use std::fs::File;
+
+#[cfg(feature = "memmap")]
+use std::io;
+
+/// Asset data in memory.
+/// Stores can provide it without copying when data is already in memory
+/// or file can be memory-mapped.
+#[derive(Clone)]
+pub struct Bytes {
+    repr: Repr,
+}
+
+#[derive(Clone)]
+enum Repr {
+    Shared(Arc<[u8]>),
+    Static(&'static [u8]),
+    #[cfg(feature = "memmap")]
+    Mapped(Arc<Mmap>),
+}
+
+impl Bytes {
+    /// Wrap static data.
+    pub fn from_static(data: &'static [u8]) -> Self {
+        Bytes {
+            repr: Repr::Static(data),
+        }
+    }
+
+    /// Map file into memory.
+    ///
+    /// # Safety
+    ///
+    /// File must not be modified or truncated while mapped by this or any other process.
+    /// Otherwise data changes under shared references
+    /// and reading past the new end of file raises `SIGBUS`.
+    #[cfg(feature = "memmap")]
+    pub unsafe fn map(file: &File) -> Result<Self, io::Error> {
+        if file.metadata()?.len() == 0 {
+            // Empty files can't be mapped.
+            return Ok(Bytes::from_static(&[]));
+        }
+        let mmap = Mmap::map(file)?;
+        Ok(Bytes {
+            repr: Repr::Mapped(Arc::new(mmap)),
+        })
+    }
+
+    /// Get data as slice.
+    pub fn as_slice(&self) -> &[u8] {
+        match self.repr {
+            Repr::Shared(ref data) => data,
+            Repr::Static(data) => data,
+            #[cfg(feature = "memmap")]
+            Repr::Mapped(ref mmap) => mmap,
+        }
+    }
+
+    /// Check if data is memory-mapped file.
+    pub fn is_mapped(&self) -> bool {
+        match self.repr {
+            #[cfg(feature = "memmap")]
+            Repr::Mapped(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Deref for Bytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for Bytes {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl fmt::Debug for Bytes {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Bytes({} bytes)", self.len())
+    }
+}
+
+impl From<Arc<[u8]>> for Bytes {
+    fn from(data: Arc<[u8]>) -> Self {
+        Bytes {
+            repr: Repr::Shared(data),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Bytes {
+    fn from(data: Vec<u8>) -> Self {
+        Bytes::from(Arc::<[u8]>::from(data))
+    }
+}
+
+impl From<&'static [u8]> for Bytes {
+    fn from(data: &'static [u8]) -> Self {
+        Bytes::from_static(data)
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{create_dir_all, File};
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use failure::Error;
+
+use path::VirtualPath;
+use store::{Bytes, Metadata, SeekRead, Store};
+
+/// `CachingStore` wraps slow store and keeps recently fetched data
+/// in memory and optionally on local disk.
+///
+/// Cached data is keyed by id and metadata reported by the wrapped store,
+/// so modified assets are fetched again.
+/// Assets of stores that provide no metadata are cached by id only and only in memory,
+/// as nothing tells if data cached on disk by previous runs is still valid.
+/// Memory cache is bounded by total size of data and evicts least recently used assets first.
+#[derive(Debug)]
+pub struct CachingStore<S> {
+    store: S,
+    capacity: usize,
+    size: usize,
+    tick: u64,
+    entries: HashMap<String, Entry>,
+    order: BTreeMap<u64, String>,
+    dir: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    data: Bytes,
+    metadata: Option<Metadata>,
+    used: u64,
+}
+
+impl<S> CachingStore<S> {
+    /// Wrap store keeping up to `capacity` bytes of data in memory.
+    pub fn new(store: S, capacity: usize) -> Self {
+        CachingStore {
+            store,
+            capacity,
+            size: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            dir: None,
+        }
+    }
+
+    /// Set directory to cache fetched data in.
+    pub fn set_cache_dir<P>(&mut self, dir: P)
+    where
+        P: Into<PathBuf>,
+    {
+        self.dir = Some(dir.into());
+    }
+
+    /// Set directory to cache fetched data in.
+    pub fn with_cache_dir<P>(mut self, dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.set_cache_dir(dir);
+        self
+    }
+
+    /// Total size of data cached in memory.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Drop all data cached in memory.
+    /// Disk cache is kept.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.size = 0;
+    }
+
+    /// Unwrap store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    fn insert(&mut self, id: String, data: Bytes, metadata: Option<Metadata>) {
+        if let Some(old) = self.entries.remove(&id) {
+            self.order.remove(&old.used);
+            self.size -= old.data.len();
+        }
+        if data.len() > self.capacity {
+            return;
+        }
+
+        while self.size + data.len() > self.capacity {
+            let (&used, _) = self
+                .order
+                .iter()
+                .next()
+                .expect("Cache can't be over capacity when empty");
+            let oldest = self.order.remove(&used).unwrap();
+            let entry = self.entries.remove(&oldest).unwrap();
+            self.size -= entry.data.len();
+            trace!("Asset {} evicted from cache", oldest);
+        }
+
+        self.tick += 1;
+        self.size += data.len();
+        self.order.insert(self.tick, id.clone());
+        self.entries.insert(
+            id,
+            Entry {
+                data,
+                metadata,
+                used: self.tick,
+            },
+        );
+    }
+
+    /// Fetch asset data from cache or the wrapped store.
+    pub fn find<I>(&mut self, id: &I) -> Result<Bytes, Error>
+    where
+        I: AsRef<Path> + ?Sized,
+        S: Store<I>,
+        S::Error: Into<Error>,
+    {
+        let path = id;
+        let id = VirtualPath::from_path(path.as_ref());
+        let metadata = self.store.metadata(path).map_err(Into::into)?;
+
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(id.as_str()) {
+            if entry.metadata == metadata {
+                let key = self.order.remove(&entry.used).unwrap();
+                self.order.insert(self.tick, key);
+                entry.used = self.tick;
+                return Ok(entry.data.clone());
+            }
+        }
+
+        // Disk cache outlives the process and is used only when metadata can validate it.
+        let disk = match (self.dir.as_ref(), metadata.as_ref()) {
+            (Some(dir), Some(metadata)) => Some((dir, metadata)),
+            _ => None,
+        };
+        let cached = disk.and_then(|(dir, metadata)| read_cache(dir, &id, metadata));
+        let data = match cached {
+            Some(data) => data,
+            None => {
+                let mut data = Vec::new();
+                self.store
+                    .fetch(path)
+                    .map_err(Into::into)?
+                    .read_to_end(&mut data)?;
+                if let Some((dir, metadata)) = disk {
+                    if let Err(err) = write_cache(dir, &id, metadata, &data) {
+                        warn!("Failed to cache {}: {}", id, err);
+                    }
+                }
+                Bytes::from(data)
+            }
+        };
+
+        self.insert(id.as_str().to_owned(), data.clone(), metadata);
+        Ok(data)
+    }
+}
+
+impl<I, S> Store<I> for CachingStore<S>
+where
+    I: AsRef<Path> + ?Sized,
+    S: Store<I>,
+    S::Error: Into<Error>,
+{
+    type Error = Error;
+    type Reader = Cursor<Bytes>;
+
+    const KIND: &'static str = S::KIND;
+
+    fn fetch(&mut self, id: &I) -> Result<Cursor<Bytes>, Error> {
+        self.find(id).map(Cursor::new)
+    }
+
+    fn fetch_seekable(&mut self, id: &I) -> Result<Option<Box<SeekRead>>, Error> {
+        Ok(Some(Box::new(Cursor::new(self.find(id)?))))
+    }
+
+    fn fetch_bytes(&mut self, id: &I) -> Result<Option<Bytes>, Error> {
+        self.find(id).map(Some)
+    }
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, Error> {
+        self.store.metadata(id).map_err(Into::into)
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, Error> {
+        self.store.list(prefix).map_err(Into::into)
+    }
+}
+
+/// Describe metadata as a line of text to compare with one stored in cache.
+fn fingerprint(metadata: &Metadata) -> String {
+    let mut fingerprint = metadata.size().to_string();
+    if let Some(modified) = metadata.modified() {
+        if let Ok(modified) = modified.duration_since(UNIX_EPOCH) {
+            fingerprint.push_str(&format!(
+                " {}.{:09}",
+                modified.as_secs(),
+                modified.subsec_nanos()
+            ));
+        }
+    }
+    #[cfg(feature = "content")]
+    {
+        if let Some(hash) = metadata.hash() {
+            fingerprint.push(' ');
+            fingerprint.push_str(&hash.to_hex());
+        }
+    }
+    fingerprint
+}
+
+fn read_cache(dir: &Path, id: &VirtualPath, metadata: &Metadata) -> Option<Bytes> {
+    let mut stored = String::new();
+    File::open(dir.join("meta").join(id.as_str()))
+        .and_then(|mut file| file.read_to_string(&mut stored))
+        .ok()?;
+    if stored != fingerprint(metadata) {
+        return None;
+    }
+
+    let mut data = Vec::new();
+    File::open(dir.join("data").join(id.as_str()))
+        .and_then(|mut file| file.read_to_end(&mut data))
+        .ok()?;
+    trace!("Asset {} found in disk cache", id);
+    Some(Bytes::from(data))
+}
+
+fn write_cache(
+    dir: &Path,
+    id: &VirtualPath,
+    metadata: &Metadata,
+    data: &[u8],
+) -> Result<(), io::Error> {
+    let data_path = dir.join("data").join(id.as_str());
+    let meta_path = dir.join("meta").join(id.as_str());
+    for path in &[&data_path, &meta_path] {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+    }
+
+    // Meta is written last so that partially written data is never used.
+    File::create(&meta_path)?.write_all(b"")?;
+    File::create(&data_path)?.write_all(data)?;
+    File::create(&meta_path)?.write_all(fingerprint(metadata).as_bytes())
+}
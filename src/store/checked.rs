@@ -0,0 +1,44 @@
+use std::io::{self, Read};
+
+use flate2::CrcReader;
+
+/// Reader that checks size and CRC-32 checksum of the data when reaching the end.
+pub struct CheckedReader<R> {
+    reader: CrcReader<R>,
+    crc: u32,
+    size: u64,
+}
+
+impl<R> CheckedReader<R>
+where
+    R: Read,
+{
+    pub fn new(reader: R, crc: u32, size: u64) -> Self {
+        CheckedReader {
+            reader: CrcReader::new(reader),
+            crc,
+            size,
+        }
+    }
+}
+
+impl<R> Read for CheckedReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let read = self.reader.read(buf)?;
+        if read == 0 && !buf.is_empty() {
+            if self.reader.crc().amount() as u64 != self.size & 0xFFFF_FFFF {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Entry size mismatch"));
+            }
+            if self.reader.crc().sum() != self.crc {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Entry checksum mismatch",
+                ));
+            }
+        }
+        Ok(read)
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs::{create_dir_all, rename, File};
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+use blake3;
+use failure::Error;
+
+use path::VirtualPath;
+use store::walk::walk;
+use store::{in_dir, Bytes, Metadata, SeekRead, Store};
+
+#[cfg(feature = "fs")]
+use store::FsStore;
+
+/// Name of the manifest file written by `ContentWriter`.
+pub const MANIFEST_NAME: &str = "manifest";
+
+/// BLAKE3 hash of asset content.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContentHash([u8; 32]);
+
+impl ContentHash {
+    /// Hash data.
+    pub fn of(data: &[u8]) -> Self {
+        ContentHash(*blake3::hash(data).as_bytes())
+    }
+
+    /// Create hash from raw bytes.
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        ContentHash(bytes)
+    }
+
+    /// Get raw bytes of the hash.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Parse hash from hex string.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if hex.len() != 64 || !hex.is_ascii() {
+            return None;
+        }
+        let mut bytes = [0; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+        }
+        Some(ContentHash(bytes))
+    }
+
+    /// Format hash as lowercase hex string.
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Verify that data has this hash.
+    /// Error is `io::Error` of `InvalidData` kind wrapping `HashMismatch`.
+    pub fn verify(&self, data: &[u8]) -> Result<(), io::Error> {
+        let actual = ContentHash::of(data);
+        if actual == *self {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                HashMismatch {
+                    expected: *self,
+                    actual,
+                },
+            ))
+        }
+    }
+
+    /// Path of the blob with this hash relative to the content directory.
+    fn blob_path(&self) -> PathBuf {
+        let hex = self.to_hex();
+        Path::new(&hex[..2]).join(&hex)
+    }
+}
+
+impl fmt::Debug for ContentHash {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "ContentHash({})", self.to_hex())
+    }
+}
+
+impl fmt::Display for ContentHash {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.to_hex())
+    }
+}
+
+/// Error of fetched data not matching expected hash.
+/// Returned wrapped into `io::Error` of `InvalidData` kind.
+#[derive(Clone, Debug)]
+pub struct HashMismatch {
+    expected: ContentHash,
+    actual: ContentHash,
+}
+
+impl HashMismatch {
+    /// Expected hash.
+    pub fn expected(&self) -> ContentHash {
+        self.expected
+    }
+
+    /// Hash of the data actually fetched.
+    pub fn actual(&self) -> ContentHash {
+        self.actual
+    }
+}
+
+impl fmt::Display for HashMismatch {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "Content hash mismatch. Expected {}, found {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl StdError for HashMismatch {
+    fn description(&self) -> &str {
+        "Content hash mismatch"
+    }
+}
+
+/// Mapping from asset ids to content hashes.
+/// Stored as text with `<hash> <id>` line per asset.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    entries: BTreeMap<String, ContentHash>,
+}
+
+impl Manifest {
+    /// Create empty manifest.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read manifest.
+    pub fn read<R>(reader: R) -> Result<Self, io::Error>
+    where
+        R: Read,
+    {
+        let mut manifest = Manifest::new();
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut split = line.splitn(2, ' ');
+            let hash = split.next().and_then(ContentHash::from_hex);
+            match (hash, split.next()) {
+                (Some(hash), Some(id)) => manifest.insert(id, hash),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid manifest line {:?}", line),
+                    ))
+                }
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Write manifest.
+    pub fn write<W>(&self, mut writer: W) -> Result<(), io::Error>
+    where
+        W: Write,
+    {
+        for (id, hash) in &self.entries {
+            writeln!(writer, "{} {}", hash, id)?;
+        }
+        Ok(())
+    }
+
+    /// Map asset id to hash.
+    pub fn insert(&mut self, id: &str, hash: ContentHash) {
+        self.entries
+            .insert(VirtualPath::new(id).as_str().to_owned(), hash);
+    }
+
+    /// Map asset id to hash.
+    pub fn with(mut self, id: &str, hash: ContentHash) -> Self {
+        self.insert(id, hash);
+        self
+    }
+
+    /// Get hash of the asset.
+    pub fn get(&self, id: &str) -> Option<ContentHash> {
+        self.entries.get(VirtualPath::new(id).as_str()).cloned()
+    }
+
+    /// Number of assets in the manifest.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if manifest is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over asset ids and their hashes.
+    pub fn iter<'a>(&'a self) -> Box<Iterator<Item = (&'a str, ContentHash)> + 'a> {
+        Box::new(self.entries.iter().map(|(id, hash)| (id.as_str(), *hash)))
+    }
+}
+
+/// Writes assets into content directory.
+/// Each unique content is stored once, in a blob named by its hash.
+#[derive(Debug)]
+pub struct ContentWriter {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+impl ContentWriter {
+    /// Create writer for directory.
+    /// Existing blobs in the directory are reused.
+    pub fn new<P>(dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        ContentWriter {
+            dir: dir.into(),
+            manifest: Manifest::new(),
+        }
+    }
+
+    /// Add asset.
+    pub fn add(&mut self, id: &str, data: &[u8]) -> Result<ContentHash, io::Error> {
+        let hash = ContentHash::of(data);
+        let path = self.dir.join(hash.blob_path());
+        if !path.is_file() {
+            create_dir_all(path.parent().unwrap())?;
+            let temp = path.with_extension("tmp");
+            File::create(&temp)?.write_all(data)?;
+            rename(temp, path)?;
+            debug!("Blob {} written", hash);
+        }
+        self.manifest.insert(id, hash);
+        Ok(hash)
+    }
+
+    /// Add file from filesystem.
+    pub fn add_file<P>(&mut self, id: &str, path: P) -> Result<ContentHash, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+        self.add(id, &data)
+    }
+
+    /// Add all files from directory recursively.
+    /// Files are added with ids `FsStore` would find them by.
+    pub fn add_dir<P>(&mut self, dir: P) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        let mut files = Vec::new();
+        walk(dir, &mut files)?;
+        for file in files {
+            let id = VirtualPath::from_path(file.strip_prefix(dir).unwrap());
+            self.add_file(id.as_str(), &file)?;
+        }
+        Ok(())
+    }
+
+    /// Write manifest file and return manifest.
+    pub fn finish(self) -> Result<Manifest, io::Error> {
+        create_dir_all(&self.dir)?;
+        self.manifest
+            .write(File::create(self.dir.join(MANIFEST_NAME))?)?;
+        Ok(self.manifest)
+    }
+}
+
+/// `ContentStore` finds assets by content hash listed in manifest.
+/// Blobs are fetched from underlying store and verified against the hash.
+#[derive(Debug)]
+pub struct ContentStore<S> {
+    store: S,
+    manifest: Manifest,
+}
+
+impl<S> ContentStore<S> {
+    /// Create store from blob store and manifest.
+    pub fn new(store: S, manifest: Manifest) -> Self {
+        ContentStore { store, manifest }
+    }
+
+    /// Get manifest.
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+}
+
+#[cfg(feature = "fs")]
+impl ContentStore<FsStore> {
+    /// Open content directory written by `ContentWriter`.
+    pub fn open<P>(dir: P) -> Result<Self, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        let manifest = Manifest::read(File::open(dir.join(MANIFEST_NAME))?)?;
+        Ok(ContentStore::new(FsStore::new().with_path(dir), manifest))
+    }
+}
+
+impl<S> ContentStore<S> {
+    /// Find asset blob and verify it.
+    pub fn find<I>(&mut self, id: &I) -> Result<Cursor<Vec<u8>>, Error>
+    where
+        I: AsRef<Path> + for<'a> From<&'a Path>,
+        S: Store<I>,
+        S::Error: Into<Error>,
+    {
+        let hash = self.hash(id.as_ref())?;
+        let mut data = Vec::new();
+        self.store
+            .fetch(&I::from(hash.blob_path().as_path()))
+            .map_err(Into::into)?
+            .read_to_end(&mut data)?;
+        hash.verify(&data)?;
+        Ok(Cursor::new(data))
+    }
+
+    /// Get metadata of asset blob with hash from manifest.
+    pub fn find_metadata<I>(&mut self, id: &I) -> Result<Option<Metadata>, Error>
+    where
+        I: AsRef<Path> + for<'a> From<&'a Path>,
+        S: Store<I>,
+        S::Error: Into<Error>,
+    {
+        let hash = self.hash(id.as_ref())?;
+        let metadata = self
+            .store
+            .metadata(&I::from(hash.blob_path().as_path()))
+            .map_err(Into::into)?;
+        Ok(metadata.map(|metadata| metadata.with_hash(hash)))
+    }
+
+    fn hash(&self, id: &Path) -> Result<ContentHash, io::Error> {
+        let id = VirtualPath::from_path(id);
+        self.manifest
+            .get(id.as_str())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+}
+
+impl<I, S> Store<I> for ContentStore<S>
+where
+    I: AsRef<Path> + for<'a> From<&'a Path>,
+    S: Store<I>,
+    S::Error: Into<Error>,
+{
+    type Error = Error;
+    type Reader = Cursor<Vec<u8>>;
+
+    const KIND: &'static str = "Content";
+
+    fn fetch(&mut self, id: &I) -> Result<Cursor<Vec<u8>>, Error> {
+        self.find(id)
+    }
+
+    fn fetch_seekable(&mut self, id: &I) -> Result<Option<Box<SeekRead>>, Error> {
+        Ok(Some(Box::new(self.find(id)?)))
+    }
+
+    fn fetch_bytes(&mut self, id: &I) -> Result<Option<Bytes>, Error> {
+        Ok(Some(Bytes::from(self.find(id)?.into_inner())))
+    }
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, Error> {
+        self.find_metadata(id)
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, Error> {
+        let prefix = VirtualPath::new(prefix);
+        Ok(self
+            .manifest
+            .iter()
+            .filter(|&(id, _)| in_dir(id, prefix.as_str()))
+            .map(|(id, _)| id.to_owned())
+            .collect())
+    }
+}
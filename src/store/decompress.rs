@@ -0,0 +1,290 @@
+use std::collections::BTreeSet;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+
+use failure::Error;
+
+#[cfg(feature = "gzip")]
+use flate2::read::GzDecoder;
+
+use store::{is_not_found, Metadata, Store};
+
+/// Compression codec detected by `DecompressStore`.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Codec {
+    /// gzip. Requires `gzip` feature.
+    Gzip,
+
+    /// Zstandard. Requires `zstd` feature.
+    Zstd,
+
+    /// LZ4 frame format. Requires `lz4` feature.
+    Lz4,
+}
+
+impl Codec {
+    /// Get codecs enabled by features.
+    pub fn enabled() -> &'static [Codec] {
+        &[
+            #[cfg(feature = "gzip")]
+            Codec::Gzip,
+            #[cfg(feature = "zstd")]
+            Codec::Zstd,
+            #[cfg(feature = "lz4")]
+            Codec::Lz4,
+        ]
+    }
+
+    /// Get file extension for the codec.
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            Codec::Gzip => "gz",
+            Codec::Zstd => "zst",
+            Codec::Lz4 => "lz4",
+        }
+    }
+
+    fn magic(&self) -> &'static [u8] {
+        match *self {
+            Codec::Gzip => &[0x1f, 0x8b],
+            Codec::Zstd => &[0x28, 0xb5, 0x2f, 0xfd],
+            Codec::Lz4 => &[0x04, 0x22, 0x4d, 0x18],
+        }
+    }
+
+    /// Find enabled codec by file extension.
+    pub fn from_extension(extension: &str) -> Option<Codec> {
+        Codec::enabled()
+            .iter()
+            .cloned()
+            .find(|codec| codec.extension() == extension)
+    }
+
+    /// Find enabled codec by magic bytes at the beginning of the data.
+    pub fn from_magic(data: &[u8]) -> Option<Codec> {
+        Codec::enabled()
+            .iter()
+            .cloned()
+            .find(|codec| data.starts_with(codec.magic()))
+    }
+
+    /// Wrap reader of compressed data into decoder.
+    #[cfg_attr(
+        not(any(feature = "gzip", feature = "zstd", feature = "lz4")),
+        allow(unused_variables)
+    )]
+    pub fn decode<R>(&self, reader: R) -> Result<Box<Read>, io::Error>
+    where
+        R: Read + 'static,
+    {
+        match *self {
+            #[cfg(feature = "gzip")]
+            Codec::Gzip => Ok(Box::new(GzDecoder::new(reader))),
+            #[cfg(feature = "zstd")]
+            Codec::Zstd => Ok(Box::new(::zstd::stream::read::Decoder::new(reader)?)),
+            #[cfg(feature = "lz4")]
+            Codec::Lz4 => Ok(Box::new(::lz4::Decoder::new(reader)?)),
+            #[allow(unreachable_patterns)]
+            codec => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("Codec {:?} is not enabled", codec),
+            )),
+        }
+    }
+}
+
+/// `DecompressStore` wraps store and transparently decompresses its data.
+///
+/// Compression is detected by id extension or by magic bytes of the data.
+/// If asset is not found by id, ids with extensions of enabled codecs appended are tried,
+/// so `"texture.png"` can be loaded from `"texture.png.zst"`.
+#[derive(Debug)]
+pub struct DecompressStore<S> {
+    store: S,
+}
+
+impl<S> DecompressStore<S> {
+    /// Wrap store.
+    pub fn new(store: S) -> Self {
+        DecompressStore { store }
+    }
+
+    /// Unwrap store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    /// Find asset and decompress its data.
+    pub fn find<I>(&mut self, id: &I) -> Result<Box<Read>, Error>
+    where
+        I: AsRef<Path> + for<'a> From<&'a Path>,
+        S: Store<I>,
+        S::Error: Into<Error>,
+        S::Reader: 'static,
+    {
+        self.locate(id)
+    }
+
+    /// Find asset and get metadata of its stored data without fetching it.
+    /// Size of decompressed data isn't stored anywhere,
+    /// so size of compressed data is reported for compressed assets
+    /// and their content hash, which describes compressed data, is dropped.
+    pub fn find_metadata<I>(&mut self, id: &I) -> Result<Option<Metadata>, Error>
+    where
+        I: AsRef<Path> + for<'a> From<&'a Path>,
+        S: Store<I>,
+        S::Error: Into<Error>,
+    {
+        let path = id.as_ref();
+        let by_extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Codec::from_extension);
+        if by_extension.is_some() {
+            let metadata = self.store.metadata(id).map_err(Into::into)?;
+            return Ok(metadata.map(compressed));
+        }
+
+        match self.store.metadata(id) {
+            Ok(metadata) => return Ok(metadata),
+            Err(err) => {
+                let err = err.into();
+                if !is_not_found(&err) {
+                    return Err(err);
+                }
+            }
+        }
+
+        for codec in Codec::enabled() {
+            let mut stored = path.as_os_str().to_owned();
+            stored.push(".");
+            stored.push(codec.extension());
+            match self.store.metadata(&I::from(Path::new(&stored))) {
+                Ok(metadata) => return Ok(metadata.map(compressed)),
+                Err(err) => {
+                    let err = err.into();
+                    if !is_not_found(&err) {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+
+        Err(io::Error::from(io::ErrorKind::NotFound).into())
+    }
+
+    /// Find asset and get decompressing reader.
+    fn locate<I>(&mut self, id: &I) -> Result<Box<Read>, Error>
+    where
+        I: AsRef<Path> + for<'a> From<&'a Path>,
+        S: Store<I>,
+        S::Error: Into<Error>,
+        S::Reader: 'static,
+    {
+        let path = id.as_ref();
+        let by_extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Codec::from_extension);
+        if let Some(codec) = by_extension {
+            let reader = self.store.fetch(id).map_err(Into::into)?;
+            return Ok(codec.decode(reader)?);
+        }
+
+        match self.store.fetch(id) {
+            Ok(reader) => return Ok(detect(reader)?),
+            Err(err) => {
+                let err = err.into();
+                if !is_not_found(&err) {
+                    return Err(err);
+                }
+            }
+        }
+
+        for codec in Codec::enabled() {
+            let mut compressed = path.as_os_str().to_owned();
+            compressed.push(".");
+            compressed.push(codec.extension());
+            let compressed = I::from(Path::new(&compressed));
+            match self.store.fetch(&compressed) {
+                Ok(reader) => return Ok(codec.decode(reader)?),
+                Err(err) => {
+                    let err = err.into();
+                    if !is_not_found(&err) {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+
+        Err(io::Error::from(io::ErrorKind::NotFound).into())
+    }
+}
+
+impl<I, S> Store<I> for DecompressStore<S>
+where
+    I: AsRef<Path> + for<'a> From<&'a Path>,
+    S: Store<I>,
+    S::Error: Into<Error>,
+    S::Reader: 'static,
+{
+    type Error = Error;
+    type Reader = Box<Read>;
+
+    const KIND: &'static str = S::KIND;
+
+    fn fetch(&mut self, id: &I) -> Result<Box<Read>, Error> {
+        self.find(id)
+    }
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, Error> {
+        self.find_metadata(id)
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, Error> {
+        let ids = self.store.list(prefix).map_err(Into::into)?;
+        let ids: BTreeSet<String> = ids
+            .into_iter()
+            .map(|id| {
+                let compressed = Codec::enabled()
+                    .iter()
+                    .find(|codec| id.ends_with(&format!(".{}", codec.extension())));
+                match compressed {
+                    Some(codec) => id[..id.len() - codec.extension().len() - 1].to_owned(),
+                    None => id,
+                }
+            })
+            .collect();
+        Ok(ids.into_iter().collect())
+    }
+}
+
+/// Keep only metadata that describes compressed data as well as decompressed one.
+fn compressed(metadata: Metadata) -> Metadata {
+    let stored = Metadata::new(metadata.size());
+    match metadata.modified() {
+        Some(modified) => stored.with_modified(modified),
+        None => stored,
+    }
+}
+
+/// Detect compression by magic bytes.
+fn detect<R>(mut reader: R) -> Result<Box<Read>, io::Error>
+where
+    R: Read + 'static,
+{
+    let mut magic = [0; 4];
+    let mut read = 0;
+    while read < magic.len() {
+        match reader.read(&mut magic[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+
+    let reader = Cursor::new(magic).take(read as u64).chain(reader);
+    match Codec::from_magic(&magic[..read]) {
+        Some(codec) => codec.decode(reader),
+        None => Ok(Box::new(reader)),
+    }
+}
@@ -0,0 +1,142 @@
+use std::fs::File;
+use std::io::{self, Cursor, Write};
+use std::path::Path;
+
+use path::VirtualPath;
+use store::walk::walk;
+use store::{in_dir, Bytes, Metadata, SeekRead, Store};
+
+/// `EmbeddedStore` fetches data embedded into the binary.
+/// Requires no filesystem access at runtime.
+///
+/// Use `write_embedded` from build script to generate entries for directory
+/// and `embedded_store!` macro to include them.
+/// Build script is rerun when files in the directory change.
+///
+/// ```ignore
+/// // build.rs
+/// asset::write_embedded("assets", Path::new(&env::var("OUT_DIR")?).join("assets.rs"))?;
+///
+/// // main.rs
+/// let store = embedded_store!("assets.rs");
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct EmbeddedStore {
+    entries: &'static [(&'static str, &'static [u8])],
+}
+
+impl EmbeddedStore {
+    /// Create store from entries.
+    /// Entries must be sorted by virtual path.
+    ///
+    /// # Panics
+    ///
+    /// Panics if entries are not sorted.
+    pub fn new(entries: &'static [(&'static str, &'static [u8])]) -> Self {
+        assert!(
+            entries.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "Entries must be sorted"
+        );
+        EmbeddedStore { entries }
+    }
+
+    /// Find entry by name.
+    pub fn find<P>(&self, path: P) -> Result<&'static [u8], io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let name = VirtualPath::from_path(path.as_ref());
+        self.entries
+            .binary_search_by(|&(entry, _)| entry.cmp(name.as_str()))
+            .map(|index| self.entries[index].1)
+            .map_err(|_| io::ErrorKind::NotFound.into())
+    }
+}
+
+impl<P> Store<P> for EmbeddedStore
+where
+    P: AsRef<Path> + ?Sized,
+{
+    type Error = io::Error;
+    type Reader = &'static [u8];
+
+    const KIND: &'static str = "Embedded";
+
+    fn fetch(&mut self, id: &P) -> Result<&'static [u8], io::Error> {
+        self.find(id)
+    }
+
+    fn metadata(&mut self, id: &P) -> Result<Option<Metadata>, io::Error> {
+        self.find(id).map(|data| Some(Metadata::new(data.len() as u64)))
+    }
+
+    fn fetch_seekable(&mut self, id: &P) -> Result<Option<Box<SeekRead>>, io::Error> {
+        Ok(Some(Box::new(Cursor::new(self.find(id)?))))
+    }
+
+    fn fetch_bytes(&mut self, id: &P) -> Result<Option<Bytes>, io::Error> {
+        self.find(id).map(|data| Some(Bytes::from_static(data)))
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        let prefix = VirtualPath::new(prefix);
+        Ok(self
+            .entries
+            .iter()
+            .filter(|&&(id, _)| in_dir(id, prefix.as_str()))
+            .map(|&(id, _)| id.to_owned())
+            .collect())
+    }
+}
+
+/// Write entries for all files in directory to be included with `embedded_store!` macro.
+/// Entries are named by paths relative to the directory, the same way `FsStore` finds them.
+/// Intended to be called from build scripts.
+/// Prints `cargo:rerun-if-changed` for the directory and every file in it,
+/// so that entries are regenerated when assets change.
+pub fn write_embedded<P, Q>(dir: P, out: Q) -> Result<(), io::Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let dir = dir.as_ref().canonicalize()?;
+    let mut files = Vec::new();
+    walk(&dir, &mut files)?;
+
+    println!("cargo:rerun-if-changed={}", dir.display());
+    for file in &files {
+        println!("cargo:rerun-if-changed={}", file.display());
+    }
+
+    let mut entries = files
+        .into_iter()
+        .map(|file| {
+            let name = VirtualPath::from_path(file.strip_prefix(&dir).unwrap());
+            (name.as_str().to_owned(), file)
+        })
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    let mut out = File::create(out)?;
+    writeln!(out, "&[")?;
+    for (name, file) in entries {
+        let file = file
+            .to_str()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Path is not UTF-8"))?;
+        writeln!(out, "    ({:?}, include_bytes!({:?})),", name, file)?;
+    }
+    writeln!(out, "]")?;
+    Ok(())
+}
+
+/// Create `EmbeddedStore` with entries generated by `write_embedded` into `OUT_DIR`.
+/// Entries generated elsewhere are included with `embedded_store!(path = "/full/path.rs")`.
+#[macro_export]
+macro_rules! embedded_store {
+    (path = $path:expr) => {
+        $crate::EmbeddedStore::new(include!($path))
+    };
+    ($file:expr) => {
+        embedded_store!(path = concat!(env!("OUT_DIR"), "/", $file))
+    };
+}
@@ -1,14 +1,57 @@
-use std::fs::File;
-use std::io;
-use std::path::{Path, PathBuf};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Write};
+use std::process;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use store::Store;
+use path::VirtualPath;
+use store::walk::walk;
+use store::{AssetSink, Metadata, SeekRead, Store};
+
+#[cfg(feature = "memmap")]
+use store::Bytes;
+
+/// Counter making names of temporary files unique within the process.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Error of fetching file outside of `FsStore` roots in strict mode.
+/// Returned wrapped into `io::Error` of `PermissionDenied` kind.
+#[derive(Debug)]
+pub struct ForbiddenPath {
+    path: PathBuf,
+}
+
+impl ForbiddenPath {
+    /// Get requested path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Display for ForbiddenPath {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Path {:?} leads outside of the store roots", self.path)
+    }
+}
+
+impl Error for ForbiddenPath {
+    fn description(&self) -> &str {
+        "Path leads outside of the store roots"
+    }
+}
 
 /// `FsStore` fetches data from files in local filesystem.
 #[derive(Debug)]
 pub struct FsStore {
     roots: Vec<PathBuf>,
     ignore_ext: bool,
+    strict: bool,
+    #[cfg(feature = "memmap")]
+    mmap: bool,
 }
 
 impl FsStore {
@@ -17,6 +60,9 @@ impl FsStore {
         FsStore {
             roots: Vec::new(),
             ignore_ext: false,
+            strict: false,
+            #[cfg(feature = "memmap")]
+            mmap: false,
         }
     }
 
@@ -37,6 +83,11 @@ impl FsStore {
         self
     }
 
+    /// Get search directories.
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
     /// Set if store should ignore extensions of files.
     pub fn set_ignore_ext(&mut self, ignore: bool) {
         self.ignore_ext = ignore;
@@ -48,23 +99,145 @@ impl FsStore {
         self
     }
 
+    /// Set if store should reject paths leading outside of its roots.
+    /// In strict mode absolute paths, `..` components and symlinks
+    /// to files outside of roots are rejected with `ForbiddenPath` error.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Set if store should reject paths leading outside of its roots.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.set_strict(strict);
+        self
+    }
+
+    /// Set if store should provide data of files by mapping them into memory.
+    /// Disabled by default.
+    ///
+    /// # Safety
+    ///
+    /// Files must not be modified or truncated while mapped.
+    /// See `Bytes::map`.
+    #[cfg(feature = "memmap")]
+    pub unsafe fn set_mmap(&mut self, mmap: bool) {
+        self.mmap = mmap;
+    }
+
+    /// Set if store should provide data of files by mapping them into memory.
+    ///
+    /// # Safety
+    ///
+    /// See `set_mmap`.
+    #[cfg(feature = "memmap")]
+    pub unsafe fn with_mmap(mut self, mmap: bool) -> Self {
+        self.set_mmap(mmap);
+        self
+    }
+
     /// Find file by name.
     pub fn find<P>(&self, path: P) -> Result<File, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.search(path.as_ref(), |path| File::open(path))
+    }
+
+    /// Find file by name and get its metadata.
+    pub fn find_metadata<P>(&self, path: P) -> Result<Metadata, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.search(path.as_ref(), |path| fs::metadata(path))
+            .map(Metadata::from)
+    }
+
+    /// Write file into the first root atomically.
+    /// Data is written into temporary file in the same directory which then replaces the target.
+    /// Paths leading outside of the root are rejected regardless of strict mode.
+    pub fn write_file<P>(&self, path: P, data: &[u8]) -> Result<(), io::Error>
     where
         P: AsRef<Path>,
     {
         let path = path.as_ref();
-        let stem = path
-            .file_stem()
-            .expect("Target must be file, not directory");
+        check_relative(path)?;
+
+        let root = self.roots.first().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "Store has no directory to write into")
+        })?;
+        let target = root.join(path);
+        let name = target
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Target must be file"))?
+            .to_owned();
+        let dir = target.parent().unwrap_or(root);
+        if self.strict {
+            // Symlinked directories are resolved before anything is created through them.
+            let root = root.canonicalize()?;
+            let mut existing = dir;
+            while !existing.exists() {
+                existing = existing.parent().unwrap_or(&root);
+            }
+            if !existing.canonicalize()?.starts_with(&root) {
+                return Err(forbidden(path));
+            }
+        }
+        fs::create_dir_all(dir)?;
+
+        let mut temp = OsString::from(".");
+        temp.push(&name);
+        temp.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp = dir.join(temp);
+
+        let result = File::create(&temp)
+            .and_then(|mut file| {
+                file.write_all(data)?;
+                file.sync_all()
+            })
+            .and_then(|()| fs::rename(&temp, &target));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        result
+    }
+
+    /// Try roots in order until `open` finds the file.
+    fn search<T, F>(&self, path: &Path, mut open: F) -> Result<T, io::Error>
+    where
+        F: FnMut(&Path) -> Result<T, io::Error>,
+    {
+        if self.strict {
+            check_relative(path)?;
+        }
+
+        let stem = path.file_stem().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Target must be file, not directory")
+        })?;
 
         for root in &self.roots {
             let mut path = root.join(path);
             if self.ignore_ext {
                 path.set_file_name(stem);
             }
-            match File::open(path) {
-                Ok(file) => return Ok(file),
+            let path = if self.strict {
+                match resolve(root, &path) {
+                    Ok(path) => path,
+                    Err(err) => {
+                        if err.kind() != io::ErrorKind::NotFound {
+                            return Err(err);
+                        }
+                        continue;
+                    }
+                }
+            } else {
+                path
+            };
+            match open(&path) {
+                Ok(found) => return Ok(found),
                 Err(err) => {
                     if err.kind() != io::ErrorKind::NotFound {
                         return Err(err);
@@ -77,6 +250,56 @@ impl FsStore {
     }
 }
 
+/// Check that path has no components leading outside of the root.
+fn check_relative(path: &Path) -> Result<(), io::Error> {
+    for component in path.components() {
+        match component {
+            Component::Normal(_) | Component::CurDir => {}
+            _ => return Err(forbidden(path)),
+        }
+    }
+    Ok(())
+}
+
+/// Check if file name is a temporary file left by `write_file`.
+fn is_temp_file(name: &OsStr) -> bool {
+    let name = match name.to_str() {
+        Some(name) => name,
+        None => return false,
+    };
+    let rest = match name.strip_suffix(".tmp") {
+        Some(rest) if name.starts_with('.') => rest,
+        _ => return false,
+    };
+    let mut parts = rest.rsplitn(3, '.');
+    let numeric = |part: Option<&str>| {
+        part.is_some_and(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+    };
+    numeric(parts.next())
+        && numeric(parts.next())
+        && parts.next().is_some_and(|name| name.len() > 1)
+}
+
+/// Resolve symlinks and check that file is inside the root.
+fn resolve(root: &Path, path: &Path) -> Result<PathBuf, io::Error> {
+    let root = root.canonicalize()?;
+    let resolved = path.canonicalize()?;
+    if resolved.starts_with(&root) {
+        Ok(resolved)
+    } else {
+        Err(forbidden(path))
+    }
+}
+
+fn forbidden(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        ForbiddenPath {
+            path: path.to_owned(),
+        },
+    )
+}
+
 impl<P> Store<P> for FsStore
 where
     P: AsRef<Path> + ?Sized,
@@ -89,4 +312,63 @@ where
     fn fetch(&mut self, id: &P) -> Result<File, io::Error> {
         self.find(id)
     }
+
+    fn metadata(&mut self, id: &P) -> Result<Option<Metadata>, io::Error> {
+        self.find_metadata(id).map(Some)
+    }
+
+    fn fetch_seekable(&mut self, id: &P) -> Result<Option<Box<SeekRead>>, io::Error> {
+        Ok(Some(Box::new(self.find(id)?)))
+    }
+
+    #[cfg(feature = "memmap")]
+    fn fetch_bytes(&mut self, id: &P) -> Result<Option<Bytes>, io::Error> {
+        if !self.mmap {
+            return Ok(None);
+        }
+        let file = self.find(id)?;
+        // Caller opted into mapping with `set_mmap` accepting its requirements.
+        unsafe { Bytes::map(&file) }.map(Some)
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        let prefix = VirtualPath::new(prefix);
+        if self.strict {
+            check_relative(Path::new(prefix.as_str()))?;
+        }
+
+        let mut ids = BTreeSet::new();
+        for root in &self.roots {
+            let dir = root.join(prefix.as_str());
+            if !dir.is_dir() {
+                continue;
+            }
+            if self.strict {
+                resolve(root, &dir)?;
+            }
+            let mut files = Vec::new();
+            walk(&dir, &mut files)?;
+            for file in files {
+                if file.file_name().is_some_and(is_temp_file) {
+                    continue;
+                }
+                if self.strict && resolve(root, &file).is_err() {
+                    // Symlinks leading outside of the root can't be fetched.
+                    continue;
+                }
+                let relative = file.strip_prefix(root).unwrap();
+                ids.insert(VirtualPath::from_path(relative).as_str().to_owned());
+            }
+        }
+        Ok(ids.into_iter().collect())
+    }
+}
+
+impl<P> AssetSink<P> for FsStore
+where
+    P: AsRef<Path> + ?Sized,
+{
+    fn write(&mut self, id: &P, data: &[u8]) -> Result<(), io::Error> {
+        self.write_file(id, data)
+    }
 }
@@ -0,0 +1,452 @@
+use std::fs::{create_dir_all, remove_file, rename, File};
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use path::VirtualPath;
+use store::{Bytes, Metadata, SeekRead, Store};
+
+/// `HttpStore` fetches assets from HTTP server.
+///
+/// Asset id is appended to the base URL.
+/// With cache directory set, fetched assets are stored on disk
+/// and revalidated with `If-None-Match` and `If-Modified-Since` headers.
+/// Cached data is also used when server is unreachable.
+/// Metadata is requested with `HEAD` request.
+/// Only plain `http://` URLs are supported.
+#[derive(Debug)]
+pub struct HttpStore {
+    host: String,
+    port: u16,
+    base: String,
+    cache: Option<PathBuf>,
+    timeout: Option<Duration>,
+}
+
+impl HttpStore {
+    /// Create new `HttpStore` for base URL.
+    pub fn new(url: &str) -> Result<Self, io::Error> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| invalid_input("Only http:// URLs are supported"))?;
+
+        let (authority, base) = match rest.find('/') {
+            Some(index) => (&rest[..index], rest[index..].trim_end_matches('/')),
+            None => (rest, ""),
+        };
+
+        let (host, port) = match authority.rfind(':') {
+            Some(index) => (
+                &authority[..index],
+                authority[index + 1..]
+                    .parse()
+                    .map_err(|_| invalid_input("Invalid port"))?,
+            ),
+            None => (authority, 80),
+        };
+
+        if host.is_empty() {
+            return Err(invalid_input("Host is not specified"));
+        }
+
+        Ok(HttpStore {
+            host: host.to_owned(),
+            port,
+            base: base.to_owned(),
+            cache: None,
+            timeout: None,
+        })
+    }
+
+    /// Set directory to cache fetched assets in.
+    pub fn set_cache_dir<P>(&mut self, dir: P)
+    where
+        P: Into<PathBuf>,
+    {
+        self.cache = Some(dir.into());
+    }
+
+    /// Set directory to cache fetched assets in.
+    pub fn with_cache_dir<P>(mut self, dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.set_cache_dir(dir);
+        self
+    }
+
+    /// Set timeout for connection reads and writes.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Set timeout for connection reads and writes.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.set_timeout(timeout);
+        self
+    }
+
+    /// Fetch asset from the server or cache.
+    pub fn find<P>(&self, id: P) -> Result<Cursor<Vec<u8>>, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let id = VirtualPath::from_path(id.as_ref());
+        let cached = self.cache.as_ref().and_then(|cache| read_meta(cache, &id));
+
+        let response = match self.request("GET", &id, cached.as_ref()) {
+            Ok(response) => response,
+            Err(err) => {
+                if cached.is_some() {
+                    warn!("Failed to fetch {} from server: {}. Using cached data", id, err);
+                    return self.read_cache(&id);
+                }
+                return Err(err);
+            }
+        };
+
+        match response.status {
+            200 => {
+                if let Some(ref cache) = self.cache {
+                    if let Err(err) = write_cache(cache, &id, &response) {
+                        warn!("Failed to cache {}: {}", id, err);
+                    }
+                }
+                Ok(Cursor::new(response.body))
+            }
+            304 if cached.is_some() => {
+                debug!("Cached {} is up to date", id);
+                self.read_cache(&id)
+            }
+            404 => Err(io::ErrorKind::NotFound.into()),
+            status => Err(io::Error::other(format!(
+                "Server responded with status {} for {}",
+                status, id
+            ))),
+        }
+    }
+
+    /// Get metadata of the asset from the server without fetching its data.
+    /// Returns `None` if server is unreachable but asset is cached.
+    pub fn find_metadata<P>(&self, id: P) -> Result<Option<Metadata>, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let id = VirtualPath::from_path(id.as_ref());
+        let response = match self.request("HEAD", &id, None) {
+            Ok(response) => response,
+            Err(err) => {
+                let cached = self
+                    .cache
+                    .as_ref()
+                    .is_some_and(|cache| cache.join("data").join(id.as_str()).is_file());
+                if cached {
+                    return Ok(None);
+                }
+                return Err(err);
+            }
+        };
+
+        match response.status {
+            200 => {
+                let metadata = Metadata::new(response.length.unwrap_or(0));
+                let modified = response
+                    .meta
+                    .last_modified
+                    .as_ref()
+                    .and_then(|modified| parse_date(modified));
+                Ok(Some(match modified {
+                    Some(modified) => metadata.with_modified(modified),
+                    None => metadata,
+                }))
+            }
+            404 => Err(io::ErrorKind::NotFound.into()),
+            status => Err(io::Error::other(format!(
+                "Server responded with status {} for {}",
+                status, id
+            ))),
+        }
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        id: &VirtualPath,
+        cached: Option<&Meta>,
+    ) -> Result<Response, io::Error> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))?;
+        stream.set_read_timeout(self.timeout)?;
+        stream.set_write_timeout(self.timeout)?;
+
+        let mut request = format!(
+            "{} {}/{} HTTP/1.1\r\nHost: {}:{}\r\nConnection: close\r\nAccept-Encoding: identity\r\n",
+            method,
+            self.base,
+            encode(id.as_str()),
+            self.host,
+            self.port
+        );
+        if let Some(cached) = cached {
+            if let Some(ref etag) = cached.etag {
+                request.push_str(&format!("If-None-Match: {}\r\n", etag));
+            }
+            if let Some(ref modified) = cached.last_modified {
+                request.push_str(&format!("If-Modified-Since: {}\r\n", modified));
+            }
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes())?;
+
+        read_response(BufReader::new(stream), method == "HEAD")
+    }
+
+    fn read_cache(&self, id: &VirtualPath) -> Result<Cursor<Vec<u8>>, io::Error> {
+        let cache = self.cache.as_ref().expect("Cache is used only when set");
+        let mut data = Vec::new();
+        File::open(cache.join("data").join(id.as_str()))?.read_to_end(&mut data)?;
+        Ok(Cursor::new(data))
+    }
+}
+
+impl<P> Store<P> for HttpStore
+where
+    P: AsRef<Path> + ?Sized,
+{
+    type Error = io::Error;
+    type Reader = Cursor<Vec<u8>>;
+
+    const KIND: &'static str = "Http";
+
+    fn fetch(&mut self, id: &P) -> Result<Cursor<Vec<u8>>, io::Error> {
+        self.find(id)
+    }
+
+    fn metadata(&mut self, id: &P) -> Result<Option<Metadata>, io::Error> {
+        self.find_metadata(id)
+    }
+
+    fn fetch_seekable(&mut self, id: &P) -> Result<Option<Box<SeekRead>>, io::Error> {
+        Ok(Some(Box::new(self.find(id)?)))
+    }
+
+    fn fetch_bytes(&mut self, id: &P) -> Result<Option<Bytes>, io::Error> {
+        Ok(Some(Bytes::from(self.find(id)?.into_inner())))
+    }
+}
+
+struct Meta {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+struct Response {
+    status: u16,
+    meta: Meta,
+    length: Option<u64>,
+    body: Vec<u8>,
+}
+
+fn read_response<R>(mut reader: R, head: bool) -> Result<Response, io::Error>
+where
+    R: BufRead,
+{
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let status = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| invalid_data("Invalid status line"))?;
+
+    let mut meta = Meta {
+        etag: None,
+        last_modified: None,
+    };
+    let mut length = None;
+    let mut chunked = false;
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let colon = line.find(':').ok_or_else(|| invalid_data("Invalid header"))?;
+        let name = line[..colon].trim().to_lowercase();
+        let value = line[colon + 1..].trim();
+        match name.as_str() {
+            "content-length" => {
+                length = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| invalid_data("Invalid content length"))?,
+                )
+            }
+            "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+            "etag" => meta.etag = Some(value.to_owned()),
+            "last-modified" => meta.last_modified = Some(value.to_owned()),
+            _ => {}
+        }
+    }
+
+    let mut body = Vec::new();
+    if head || status == 304 || status == 204 {
+        // No body is sent with these responses.
+    } else if chunked {
+        loop {
+            line.clear();
+            reader.read_line(&mut line)?;
+            let size = line.trim().split(';').next().unwrap_or("");
+            let size =
+                u64::from_str_radix(size, 16).map_err(|_| invalid_data("Invalid chunk size"))?;
+            if size == 0 {
+                break;
+            }
+            let read = (&mut reader).take(size).read_to_end(&mut body)?;
+            if (read as u64) < size {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            line.clear();
+            reader.read_line(&mut line)?;
+        }
+    } else if let Some(length) = length {
+        (&mut reader).take(length).read_to_end(&mut body)?;
+        if (body.len() as u64) < length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+    } else {
+        reader.read_to_end(&mut body)?;
+    }
+
+    Ok(Response {
+        status,
+        meta,
+        length,
+        body,
+    })
+}
+
+fn read_meta(cache: &Path, id: &VirtualPath) -> Option<Meta> {
+    if !cache.join("data").join(id.as_str()).is_file() {
+        return None;
+    }
+
+    let mut text = String::new();
+    File::open(cache.join("meta").join(id.as_str()))
+        .ok()?
+        .read_to_string(&mut text)
+        .ok()?;
+
+    let mut meta = Meta {
+        etag: None,
+        last_modified: None,
+    };
+    for line in text.lines() {
+        if let Some(etag) = line.strip_prefix("etag: ") {
+            meta.etag = Some(etag.to_owned());
+        } else if let Some(modified) = line.strip_prefix("last-modified: ") {
+            meta.last_modified = Some(modified.to_owned());
+        }
+    }
+    Some(meta)
+}
+
+fn write_cache(cache: &Path, id: &VirtualPath, response: &Response) -> Result<(), io::Error> {
+    let data = cache.join("data").join(id.as_str());
+    let meta = cache.join("meta").join(id.as_str());
+    for path in &[&data, &meta] {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+    }
+
+    // Meta is invalidated first and replaced last,
+    // so interrupted write never pairs old validators with new data.
+    if let Err(err) = remove_file(&meta) {
+        if err.kind() != io::ErrorKind::NotFound {
+            return Err(err);
+        }
+    }
+    write_atomic(&data, &response.body)?;
+
+    let mut text = Vec::new();
+    if let Some(ref etag) = response.meta.etag {
+        writeln!(text, "etag: {}", etag)?;
+    }
+    if let Some(ref modified) = response.meta.last_modified {
+        writeln!(text, "last-modified: {}", modified)?;
+    }
+    write_atomic(&meta, &text)
+}
+
+/// Write file into temporary one next to it and rename it over the target.
+fn write_atomic(path: &Path, data: &[u8]) -> Result<(), io::Error> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", process::id()));
+    let temp = PathBuf::from(temp);
+
+    let result = File::create(&temp)
+        .and_then(|mut file| file.write_all(data))
+        .and_then(|()| rename(&temp, path));
+    if result.is_err() {
+        let _ = remove_file(&temp);
+    }
+    result
+}
+
+/// Percent-encode path keeping `/` separators.
+fn encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for &byte in path.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            byte => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Parse HTTP date in IMF-fixdate format, e.g. `Fri, 01 Jun 2018 12:30:00 GMT`.
+fn parse_date(date: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let parts: Vec<&str> = date.split_whitespace().collect();
+    if parts.len() != 6 || parts[5] != "GMT" {
+        return None;
+    }
+    let day: i64 = parts[1].parse().ok()?;
+    let month = MONTHS.iter().position(|&month| month == parts[2])? as i64 + 1;
+    let year: i64 = parts[3].parse().ok()?;
+    let time = parts[4]
+        .split(':')
+        .map(|part| part.parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    if year < 1970 || !(1..=31).contains(&day) || time.len() != 3 {
+        return None;
+    }
+
+    // Days since epoch of the proleptic Gregorian calendar date.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86_400 + time[0] * 3600 + time[1] * 60 + time[2];
+    Some(UNIX_EPOCH + Duration::from_secs(seconds as u64))
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::{self, Cursor};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use id::AssetId;
+use path::VirtualPath;
+use store::{in_dir, AssetSink, Bytes, Metadata, SeekRead, Store};
+
+/// `MemoryStore` fetches data from memory.
+/// Clones of the store share data,
+/// so assets can be inserted and removed after store is added to `Assets`.
+#[derive(Debug)]
+pub struct MemoryStore<I = AssetId> {
+    data: Arc<RwLock<HashMap<I, Arc<[u8]>>>>,
+}
+
+impl<I> Clone for MemoryStore<I> {
+    fn clone(&self) -> Self {
+        MemoryStore {
+            data: Arc::clone(&self.data),
+        }
+    }
+}
+
+impl<I> Default for MemoryStore<I>
+where
+    I: Hash + Eq,
+{
+    fn default() -> Self {
+        MemoryStore {
+            data: Default::default(),
+        }
+    }
+}
+
+impl<I> MemoryStore<I>
+where
+    I: Hash + Eq,
+{
+    /// Create new empty `MemoryStore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert asset data.
+    /// Returns previous data if any.
+    pub fn insert<D>(&self, id: I, data: D) -> Option<Arc<[u8]>>
+    where
+        D: Into<Arc<[u8]>>,
+    {
+        self.data.write().unwrap().insert(id, data.into())
+    }
+
+    /// Insert asset data.
+    pub fn with<D>(self, id: I, data: D) -> Self
+    where
+        D: Into<Arc<[u8]>>,
+    {
+        self.insert(id, data);
+        self
+    }
+
+    /// Remove asset data.
+    /// Returns removed data if any.
+    pub fn remove(&self, id: &I) -> Option<Arc<[u8]>> {
+        self.data.write().unwrap().remove(id)
+    }
+
+    /// Get asset data.
+    pub fn get(&self, id: &I) -> Option<Arc<[u8]>> {
+        self.data.read().unwrap().get(id).cloned()
+    }
+}
+
+impl<I> Store<I> for MemoryStore<I>
+where
+    I: Hash + Eq + AsRef<Path>,
+{
+    type Error = io::Error;
+    type Reader = Cursor<Arc<[u8]>>;
+
+    const KIND: &'static str = "Memory";
+
+    fn fetch(&mut self, id: &I) -> Result<Cursor<Arc<[u8]>>, io::Error> {
+        self.get(id)
+            .map(Cursor::new)
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, io::Error> {
+        self.get(id)
+            .map(|data| Some(Metadata::new(data.len() as u64)))
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    fn fetch_seekable(&mut self, id: &I) -> Result<Option<Box<SeekRead>>, io::Error> {
+        self.fetch(id).map(|reader| Some(Box::new(reader) as Box<SeekRead>))
+    }
+
+    fn fetch_bytes(&mut self, id: &I) -> Result<Option<Bytes>, io::Error> {
+        self.get(id)
+            .map(|data| Some(Bytes::from(data)))
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        let prefix = VirtualPath::new(prefix);
+        let mut ids: Vec<String> = self
+            .data
+            .read()
+            .unwrap()
+            .keys()
+            .map(|id| VirtualPath::from_path(id.as_ref()).as_str().to_owned())
+            .filter(|id| in_dir(id, prefix.as_str()))
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+impl<I> AssetSink<I> for MemoryStore<I>
+where
+    I: Hash + Eq + Clone + AsRef<Path>,
+{
+    fn write(&mut self, id: &I, data: &[u8]) -> Result<(), io::Error> {
+        self.insert(id.clone(), data.to_vec());
+        Ok(())
+    }
+}
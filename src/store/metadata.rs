@@ -0,0 +1,65 @@
+use std::fs;
+use std::time::SystemTime;
+
+#[cfg(feature = "content")]
+use store::ContentHash;
+
+/// Asset metadata that store can provide without fetching the data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    size: u64,
+    modified: Option<SystemTime>,
+    #[cfg(feature = "content")]
+    hash: Option<ContentHash>,
+}
+
+impl Metadata {
+    /// Create metadata of asset with specified size of data.
+    pub fn new(size: u64) -> Self {
+        Metadata {
+            size,
+            modified: None,
+            #[cfg(feature = "content")]
+            hash: None,
+        }
+    }
+
+    /// Set modification time.
+    pub fn with_modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    /// Set content hash.
+    #[cfg(feature = "content")]
+    pub fn with_hash(mut self, hash: ContentHash) -> Self {
+        self.hash = Some(hash);
+        self
+    }
+
+    /// Size of the asset data in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Last modification time if known.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// Expected hash of the asset data if known.
+    #[cfg(feature = "content")]
+    pub fn hash(&self) -> Option<ContentHash> {
+        self.hash
+    }
+}
+
+impl From<fs::Metadata> for Metadata {
+    fn from(metadata: fs::Metadata) -> Self {
+        let result = Metadata::new(metadata.len());
+        match metadata.modified() {
+            Ok(modified) => result.with_modified(modified),
+            Err(_) => result,
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use id::AssetId;
+use store::{Metadata, Store};
+
+/// Fault `MockStore` can be scripted to inject for an asset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// Asset is not found even if its data is present.
+    NotFound,
+
+    /// Fetching and metadata requests fail with error of specified kind.
+    Error(io::ErrorKind),
+
+    /// Reading fails with error of specified kind after specified number of bytes is read.
+    ErrorAfter(usize, io::ErrorKind),
+
+    /// Data is cut to specified number of bytes.
+    /// Metadata reports size of the cut data.
+    Truncate(usize),
+
+    /// Fetching and metadata requests block for specified time before proceeding.
+    Delay(Duration),
+}
+
+/// `MockStore` serves data from memory and injects scripted faults.
+/// Intended for testing how code handles misbehaving stores.
+/// Clones of the store share data and faults,
+/// so they can be changed after store is added to `Assets`.
+#[derive(Debug)]
+pub struct MockStore<I = AssetId> {
+    state: Arc<Mutex<State<I>>>,
+}
+
+#[derive(Debug)]
+struct State<I> {
+    data: HashMap<I, Arc<[u8]>>,
+    faults: HashMap<I, Vec<Fault>>,
+}
+
+impl<I> Clone for MockStore<I> {
+    fn clone(&self) -> Self {
+        MockStore {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<I> Default for MockStore<I>
+where
+    I: Hash + Eq,
+{
+    fn default() -> Self {
+        MockStore {
+            state: Arc::new(Mutex::new(State {
+                data: HashMap::new(),
+                faults: HashMap::new(),
+            })),
+        }
+    }
+}
+
+impl<I> MockStore<I>
+where
+    I: Hash + Eq,
+{
+    /// Create new empty `MockStore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert asset data.
+    pub fn insert<D>(&self, id: I, data: D)
+    where
+        D: Into<Arc<[u8]>>,
+    {
+        self.state.lock().unwrap().data.insert(id, data.into());
+    }
+
+    /// Insert asset data.
+    pub fn with<D>(self, id: I, data: D) -> Self
+    where
+        D: Into<Arc<[u8]>>,
+    {
+        self.insert(id, data);
+        self
+    }
+
+    /// Add fault injected for the asset.
+    /// Faults are applied in order they were added.
+    pub fn add_fault(&self, id: I, fault: Fault) {
+        self.state
+            .lock()
+            .unwrap()
+            .faults
+            .entry(id)
+            .or_insert_with(Vec::new)
+            .push(fault);
+    }
+
+    /// Add fault injected for the asset.
+    pub fn with_fault(self, id: I, fault: Fault) -> Self {
+        self.add_fault(id, fault);
+        self
+    }
+
+    /// Remove all faults of the asset.
+    pub fn clear_faults(&self, id: &I) {
+        self.state.lock().unwrap().faults.remove(id);
+    }
+
+    /// Get data and faults of the asset.
+    fn script(&self, id: &I) -> (Option<Arc<[u8]>>, Vec<Fault>) {
+        let state = self.state.lock().unwrap();
+        (
+            state.data.get(id).cloned(),
+            state.faults.get(id).cloned().unwrap_or_default(),
+        )
+    }
+}
+
+impl<I> Store<I> for MockStore<I>
+where
+    I: Hash + Eq + AsRef<Path>,
+{
+    type Error = io::Error;
+    type Reader = MockReader;
+
+    const KIND: &'static str = "Mock";
+
+    fn fetch(&mut self, id: &I) -> Result<MockReader, io::Error> {
+        let (data, faults) = self.script(id);
+        let data = data.ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        let mut reader = MockReader {
+            end: data.len(),
+            data,
+            position: 0,
+            error: None,
+        };
+
+        for fault in faults {
+            match fault {
+                Fault::NotFound => return Err(io::ErrorKind::NotFound.into()),
+                Fault::Error(kind) => return Err(injected(kind)),
+                Fault::ErrorAfter(offset, kind) => reader.error = Some((offset, kind)),
+                Fault::Truncate(len) => reader.end = reader.end.min(len),
+                Fault::Delay(delay) => thread::sleep(delay),
+            }
+        }
+        Ok(reader)
+    }
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, io::Error> {
+        let (data, faults) = self.script(id);
+        let mut size = data
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?
+            .len();
+
+        for fault in faults {
+            match fault {
+                Fault::NotFound => return Err(io::ErrorKind::NotFound.into()),
+                Fault::Error(kind) => return Err(injected(kind)),
+                // Reading errors don't affect metadata.
+                Fault::ErrorAfter(_, _) => {}
+                Fault::Truncate(len) => size = size.min(len),
+                Fault::Delay(delay) => thread::sleep(delay),
+            }
+        }
+        Ok(Some(Metadata::new(size as u64)))
+    }
+}
+
+/// Reader of data fetched from `MockStore`.
+#[derive(Debug)]
+pub struct MockReader {
+    data: Arc<[u8]>,
+    position: usize,
+    end: usize,
+    error: Option<(usize, io::ErrorKind)>,
+}
+
+impl Read for MockReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let mut end = self.end;
+        if let Some((offset, kind)) = self.error {
+            if self.position >= offset {
+                return Err(injected(kind));
+            }
+            end = end.min(offset);
+        }
+
+        let read = buf.len().min(end.saturating_sub(self.position));
+        buf[..read].copy_from_slice(&self.data[self.position..self.position + read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+fn injected(kind: io::ErrorKind) -> io::Error {
+    io::Error::new(kind, "Fault injected by MockStore")
+}
@@ -1,18 +1,90 @@
 #[cfg(feature = "futures")]
 mod async;
 
+#[cfg(all(feature = "futures", feature = "fs"))]
+mod async_fs;
+
+mod bytes;
+mod caching;
+
+#[cfg(any(feature = "zip", feature = "pack"))]
+mod checked;
+
+#[cfg(feature = "content")]
+mod content;
+
+mod decompress;
+
 #[cfg(feature = "fs")]
 mod fs;
 
-use std::path::PathBuf;
+mod embedded;
+
+#[cfg(feature = "http")]
+mod http;
+
+mod memory;
+mod metadata;
+mod mock;
+mod overlay;
+
+#[cfg(feature = "pack")]
+mod pack;
+
+mod recording;
+
+#[cfg(feature = "sealed")]
+mod sealed;
+
+mod walk;
+
+#[cfg(feature = "zip")]
+mod zip;
+
+use id::AssetId;
 
 #[cfg(feature = "fs")]
-pub use self::fs::FsStore;
+pub use self::fs::{ForbiddenPath, FsStore};
+
+pub use self::bytes::Bytes;
+pub use self::caching::CachingStore;
+
+#[cfg(feature = "content")]
+pub use self::content::{
+    ContentHash, ContentStore, ContentWriter, HashMismatch, Manifest, MANIFEST_NAME,
+};
+
+pub use self::decompress::{Codec, DecompressStore};
+pub use self::embedded::{write_embedded, EmbeddedStore};
+
+#[cfg(feature = "http")]
+pub use self::http::HttpStore;
+
+pub use self::memory::MemoryStore;
+pub use self::metadata::Metadata;
+pub use self::mock::{Fault, MockReader, MockStore};
+pub use self::overlay::{OverlayStore, WHITEOUT_PREFIX};
+
+#[cfg(feature = "pack")]
+pub use self::pack::{PackCompression, PackReader, PackStore, PackWriter};
+
+pub use self::recording::{Fetch, FetchKind, FetchLog, RecordingStore};
+
+#[cfg(feature = "sealed")]
+pub use self::sealed::{IntegrityError, SealKey, SealedStore};
+
+#[cfg(feature = "zip")]
+pub use self::zip::{ZipReader, ZipStore};
 
 #[cfg(feature = "futures")]
 pub use self::async::{AsyncStore, AsyncStoreWrapper};
 
-use std::io::Read;
+#[cfg(all(feature = "futures", feature = "fs"))]
+pub use self::async_fs::{AsyncFsReader, AsyncFsStore};
+
+use std::io::{self, Read, Seek};
+
+use failure::Error;
 
 /// Virtual container for assets.
 /// Store can be represented by filesystem, archive, content server and so on.
@@ -20,7 +92,7 @@ use std::io::Read;
 /// # Parameters
 /// `I` - identifier type the `Store` uses to identify assets.
 ///
-pub trait Store<I: ?Sized = PathBuf> {
+pub trait Store<I: ?Sized = AssetId> {
     /// Possible error type.
     type Error;
 
@@ -33,4 +105,62 @@ pub trait Store<I: ?Sized = PathBuf> {
     /// Fetch asset data from the store.
     /// Returns reader object that yields raw data of the asset.
     fn fetch(&mut self, id: &I) -> Result<Self::Reader, Self::Error>;
+
+    /// Get asset metadata without fetching the data.
+    /// Returns `None` if store doesn't provide metadata,
+    /// which says nothing about presence of the asset.
+    fn metadata(&mut self, _id: &I) -> Result<Option<Metadata>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Fetch asset data with random access.
+    /// Returns `None` if store can't provide seekable reader,
+    /// then caller should buffer data from `fetch`.
+    fn fetch_seekable(&mut self, _id: &I) -> Result<Option<Box<SeekRead>>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Fetch asset data in memory without copying.
+    /// Returns `None` if store can't provide data this way,
+    /// then caller should buffer data from `fetch`.
+    fn fetch_bytes(&mut self, _id: &I) -> Result<Option<Bytes>, Self::Error> {
+        Ok(None)
+    }
+
+    /// List ids of assets in `prefix` directory recursively.
+    /// Ids are normalized virtual paths. Empty prefix lists all assets.
+    /// Stores that can't enumerate assets return empty list.
+    fn list(&mut self, _prefix: &str) -> Result<Vec<String>, Self::Error> {
+        Ok(Vec::new())
+    }
+}
+
+/// Store that assets can be written to.
+pub trait AssetSink<I: ?Sized = AssetId>: Store<I> {
+    /// Write asset data replacing existing one.
+    /// Readers should never observe partially written data.
+    fn write(&mut self, id: &I, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Reader with random access.
+pub trait SeekRead: Read + Seek {}
+
+impl<R> SeekRead for R
+where
+    R: Read + Seek,
+{
+}
+
+/// Check if normalized id is inside normalized directory.
+pub(crate) fn in_dir(id: &str, dir: &str) -> bool {
+    dir.is_empty() || (id.starts_with(dir) && id[dir.len()..].starts_with('/'))
+}
+
+/// Check if error is caused by `io::Error` of `NotFound` kind.
+pub(crate) fn is_not_found(err: &Error) -> bool {
+    err.iter_chain().any(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .map_or(false, |err| err.kind() == io::ErrorKind::NotFound)
+    })
 }
@@ -0,0 +1,309 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{self, Read};
+use std::path::Path;
+
+use failure::Error;
+
+use id::AssetId;
+use path::VirtualPath;
+use store::{is_not_found, Bytes, Metadata, SeekRead, Store};
+
+/// Prefix of the whiteout marker file name.
+/// Marker `dir/.wh.name` in a layer hides `dir/name` in all layers with lower priority.
+pub const WHITEOUT_PREFIX: &str = ".wh.";
+
+trait AnyLayer<I> {
+    fn fetch(&mut self, id: &I) -> Result<Option<Box<Read>>, Error>;
+    fn fetch_seekable(&mut self, id: &I) -> Result<Option<Option<Box<SeekRead>>>, Error>;
+    fn fetch_bytes(&mut self, id: &I) -> Result<Option<Option<Bytes>>, Error>;
+    fn metadata(&mut self, id: &I) -> Result<Option<Option<Metadata>>, Error>;
+    fn exists(&mut self, id: &I) -> Result<bool, Error>;
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, Error>;
+}
+
+impl<I, S> AnyLayer<I> for S
+where
+    S: Store<I>,
+    S::Error: Into<Error>,
+    S::Reader: 'static,
+{
+    fn fetch(&mut self, id: &I) -> Result<Option<Box<Read>>, Error> {
+        match Store::fetch(self, id) {
+            Ok(reader) => Ok(Some(Box::new(reader))),
+            Err(err) => fetch_error(err.into(), S::KIND),
+        }
+    }
+
+    fn fetch_seekable(&mut self, id: &I) -> Result<Option<Option<Box<SeekRead>>>, Error> {
+        match Store::fetch_seekable(self, id) {
+            Ok(Some(reader)) => Ok(Some(Some(reader))),
+            // Store can't provide seekable reader, check that it has the asset at all.
+            Ok(None) => Ok(if AnyLayer::exists(self, id)? { Some(None) } else { None }),
+            Err(err) => fetch_error(err.into(), S::KIND),
+        }
+    }
+
+    fn fetch_bytes(&mut self, id: &I) -> Result<Option<Option<Bytes>>, Error> {
+        match Store::fetch_bytes(self, id) {
+            Ok(Some(bytes)) => Ok(Some(Some(bytes))),
+            Ok(None) => Ok(if AnyLayer::exists(self, id)? { Some(None) } else { None }),
+            Err(err) => fetch_error(err.into(), S::KIND),
+        }
+    }
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Option<Metadata>>, Error> {
+        match Store::metadata(self, id) {
+            Ok(Some(metadata)) => Ok(Some(Some(metadata))),
+            // Store doesn't provide metadata, check that it has the asset at all.
+            Ok(None) => Ok(AnyLayer::fetch(self, id)?.map(|_| None)),
+            Err(err) => {
+                let err = err.into();
+                if is_not_found(&err) {
+                    Ok(None)
+                } else {
+                    Err(err
+                        .context(format!("Failed to get metadata from <{}> store", S::KIND))
+                        .into())
+                }
+            }
+        }
+    }
+
+    fn exists(&mut self, id: &I) -> Result<bool, Error> {
+        AnyLayer::metadata(self, id).map(|metadata| metadata.is_some())
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, Error> {
+        Store::list(self, prefix).map_err(|e| {
+            e.into()
+                .context(format!("Failed to list assets of <{}> store", S::KIND))
+                .into()
+        })
+    }
+}
+
+/// Treat missing asset as `None` and add store kind to other errors.
+fn fetch_error<T>(err: Error, kind: &str) -> Result<Option<T>, Error> {
+    if is_not_found(&err) {
+        Ok(None)
+    } else {
+        Err(err
+            .context(format!("Failed to fetch asset from <{}> store", kind))
+            .into())
+    }
+}
+
+struct Layer<I> {
+    name: String,
+    priority: i32,
+    store: Box<AnyLayer<I> + Send + Sync>,
+}
+
+/// `OverlayStore` combines stores as layers.
+/// Layers with higher priority override assets of layers with lower priority.
+/// Layers with equal priority are tried in order they were added.
+/// Layer can hide assets of lower layers with whiteout markers (see `WHITEOUT_PREFIX`).
+pub struct OverlayStore<I = AssetId> {
+    layers: Vec<Layer<I>>,
+    served: HashMap<String, usize>,
+}
+
+impl<I> OverlayStore<I> {
+    /// Create new `OverlayStore` without layers.
+    pub fn new() -> Self {
+        OverlayStore {
+            layers: Vec::new(),
+            served: HashMap::new(),
+        }
+    }
+
+    /// Add layer with priority.
+    pub fn add_layer<S>(&mut self, name: &str, priority: i32, store: S)
+    where
+        S: Store<I> + Send + Sync + 'static,
+        S::Error: Into<Error>,
+        S::Reader: 'static,
+    {
+        let index = self
+            .layers
+            .iter()
+            .position(|layer| layer.priority < priority)
+            .unwrap_or(self.layers.len());
+        self.layers.insert(
+            index,
+            Layer {
+                name: name.to_owned(),
+                priority,
+                store: Box::new(store),
+            },
+        );
+        self.served.clear();
+        info!("Layer {} with priority {} added", name, priority);
+    }
+
+    /// Add layer with priority.
+    pub fn with_layer<S>(mut self, name: &str, priority: i32, store: S) -> Self
+    where
+        S: Store<I> + Send + Sync + 'static,
+        S::Error: Into<Error>,
+        S::Reader: 'static,
+    {
+        self.add_layer(name, priority, store);
+        self
+    }
+
+    /// Get name of the layer that served the asset last time it was fetched.
+    pub fn served_by<P>(&self, id: P) -> Option<&str>
+    where
+        P: AsRef<Path>,
+    {
+        self.served
+            .get(VirtualPath::from_path(id.as_ref()).as_str())
+            .map(|&index| self.layers[index].name.as_str())
+    }
+
+    /// List assets of all layers in directory.
+    /// Assets hidden by whiteouts and whiteout markers themselves are not listed.
+    pub fn list_all(&mut self, prefix: &str) -> Result<Vec<String>, Error> {
+        let mut ids = BTreeSet::new();
+        let mut hidden = HashSet::new();
+        for layer in &mut self.layers {
+            let mut whiteouts = Vec::new();
+            for id in layer.store.list(prefix)? {
+                let (dir, name) = match id.rfind('/') {
+                    Some(index) => id.split_at(index + 1),
+                    None => ("", id.as_str()),
+                };
+                if name.starts_with(WHITEOUT_PREFIX) {
+                    whiteouts.push(format!("{}{}", dir, &name[WHITEOUT_PREFIX.len()..]));
+                } else if !hidden.contains(&id) {
+                    ids.insert(id.clone());
+                }
+            }
+            hidden.extend(whiteouts);
+        }
+        Ok(ids.into_iter().collect())
+    }
+}
+
+impl<I> OverlayStore<I>
+where
+    I: for<'a> From<&'a Path>,
+{
+    /// Find asset in layers.
+    pub fn find<P>(&mut self, id: P) -> Result<Box<Read>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.serve(id.as_ref(), |layer, id| layer.fetch(id))
+    }
+
+    /// Find asset in layers and get seekable reader if serving layer can provide one.
+    pub fn find_seekable<P>(&mut self, id: P) -> Result<Option<Box<SeekRead>>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.serve(id.as_ref(), |layer, id| layer.fetch_seekable(id))
+    }
+
+    /// Find asset in layers and get its data in memory if serving layer can provide it.
+    pub fn find_bytes<P>(&mut self, id: P) -> Result<Option<Bytes>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.serve(id.as_ref(), |layer, id| layer.fetch_bytes(id))
+    }
+
+    /// Try layers in order until `fetch` finds the asset or it is hidden by whiteout.
+    fn serve<T, F>(&mut self, path: &Path, mut fetch: F) -> Result<T, Error>
+    where
+        F: FnMut(&mut AnyLayer<I>, &I) -> Result<Option<T>, Error>,
+    {
+        let id = I::from(path);
+        let key = VirtualPath::from_path(path).as_str().to_owned();
+        let whiteout = whiteout(path);
+
+        for (index, layer) in self.layers.iter_mut().enumerate() {
+            if let Some(found) = fetch(&mut *layer.store, &id)? {
+                debug!("Asset {:?} is served by layer {}", path, layer.name);
+                self.served.insert(key, index);
+                return Ok(found);
+            }
+
+            if let Some(ref whiteout) = whiteout {
+                if layer.store.exists(whiteout)? {
+                    debug!("Asset {:?} is hidden by layer {}", path, layer.name);
+                    break;
+                }
+            }
+        }
+
+        self.served.remove(&key);
+        Err(io::Error::from(io::ErrorKind::NotFound).into())
+    }
+
+    /// Find asset in layers and get its metadata.
+    /// Returns `None` if layer that has the asset doesn't provide metadata.
+    pub fn find_metadata<P>(&mut self, id: P) -> Result<Option<Metadata>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = id.as_ref();
+        let id = I::from(path);
+        let whiteout = whiteout(path);
+
+        for layer in &mut self.layers {
+            if let Some(metadata) = layer.store.metadata(&id)? {
+                return Ok(metadata);
+            }
+
+            if let Some(ref whiteout) = whiteout {
+                if layer.store.exists(whiteout)? {
+                    break;
+                }
+            }
+        }
+
+        Err(io::Error::from(io::ErrorKind::NotFound).into())
+    }
+}
+
+impl<P, I> Store<P> for OverlayStore<I>
+where
+    P: AsRef<Path> + ?Sized,
+    I: for<'a> From<&'a Path>,
+{
+    type Error = Error;
+    type Reader = Box<Read>;
+
+    const KIND: &'static str = "Overlay";
+
+    fn fetch(&mut self, id: &P) -> Result<Box<Read>, Error> {
+        self.find(id)
+    }
+
+    fn fetch_seekable(&mut self, id: &P) -> Result<Option<Box<SeekRead>>, Error> {
+        self.find_seekable(id)
+    }
+
+    fn fetch_bytes(&mut self, id: &P) -> Result<Option<Bytes>, Error> {
+        self.find_bytes(id)
+    }
+
+    fn metadata(&mut self, id: &P) -> Result<Option<Metadata>, Error> {
+        self.find_metadata(id)
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, Error> {
+        self.list_all(prefix)
+    }
+}
+
+fn whiteout<I>(id: &Path) -> Option<I>
+where
+    I: for<'a> From<&'a Path>,
+{
+    let name = id.file_name()?.to_str()?;
+    let whiteout = id.with_file_name(format!("{}{}", WHITEOUT_PREFIX, name));
+    Some(I::from(whiteout.as_path()))
+}
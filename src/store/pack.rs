@@ -0,0 +1,432 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Take, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Crc;
+
+#[cfg(feature = "fs")]
+use store::FsStore;
+
+use path::VirtualPath;
+use store::checked::CheckedReader;
+use store::walk::walk;
+use store::{in_dir, Metadata, Store};
+
+const MAGIC: &[u8; 4] = b"ASPK";
+const VERSION: u32 = 1;
+const HEADER_SIZE: u64 = 20;
+
+/// Compression of the pack entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackCompression {
+    /// Entry is stored as is.
+    None,
+
+    /// Entry is compressed with deflate.
+    Deflate,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    name: String,
+    offset: u64,
+    size: u64,
+    stored_size: u64,
+    compression: PackCompression,
+    crc: u32,
+}
+
+/// `PackStore` fetches data from the pack file.
+///
+/// Pack file consists of header, entries data and index of entries sorted by virtual path.
+/// Index is read once when pack is opened.
+/// Each entry can be compressed and has size and CRC-32 checksum of uncompressed data.
+#[derive(Debug)]
+pub struct PackStore {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl PackStore {
+    /// Open pack file and read its index.
+    pub fn open<P>(path: P) -> Result<Self, io::Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let mut file = BufReader::new(File::open(&path)?);
+
+        let mut header = [0; HEADER_SIZE as usize];
+        file.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("Not a pack file"));
+        }
+        let version = read_u32(&mut &header[4..])?;
+        if version != VERSION {
+            return Err(invalid("Unsupported pack version"));
+        }
+        let index_offset = read_u64(&mut &header[8..])?;
+        let count = read_u32(&mut &header[16..])?;
+
+        file.seek(SeekFrom::Start(index_offset))?;
+        // Count comes from untrusted header, so vector grows as entries are actually read.
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let entry = read_entry(&mut file)?;
+            entries.push(entry);
+        }
+
+        if entries.windows(2).any(|pair| pair[0].name >= pair[1].name) {
+            return Err(invalid("Pack index is not sorted"));
+        }
+
+        info!("Pack {:?} with {} entries opened", path, entries.len());
+        Ok(PackStore { path, entries })
+    }
+
+    /// Find entry by name and get its metadata.
+    /// Entries don't have own modification time, pack file's one is reported.
+    pub fn find_metadata<P>(&self, path: P) -> Result<Metadata, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let entry = self.entry(path.as_ref())?;
+        let metadata = Metadata::new(entry.size);
+        Ok(match fs::metadata(&self.path)?.modified() {
+            Ok(modified) => metadata.with_modified(modified),
+            Err(_) => metadata,
+        })
+    }
+
+    /// Find entry by name.
+    pub fn find<P>(&self, path: P) -> Result<PackReader, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let entry = self.entry(path.as_ref())?;
+
+        let mut file = BufReader::new(File::open(&self.path)?);
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let data = file.take(entry.stored_size);
+        let reader = match entry.compression {
+            PackCompression::None => Decoder::None(data),
+            PackCompression::Deflate => Decoder::Deflate(DeflateDecoder::new(data)),
+        };
+
+        Ok(PackReader {
+            reader: CheckedReader::new(reader, entry.crc, entry.size),
+        })
+    }
+
+    fn entry(&self, path: &Path) -> Result<&Entry, io::Error> {
+        let name = VirtualPath::from_path(path);
+        self.entries
+            .binary_search_by(|entry| entry.name.as_str().cmp(name.as_str()))
+            .map(|index| &self.entries[index])
+            .map_err(|_| io::ErrorKind::NotFound.into())
+    }
+}
+
+impl<P> Store<P> for PackStore
+where
+    P: AsRef<Path> + ?Sized,
+{
+    type Error = io::Error;
+    type Reader = PackReader;
+
+    const KIND: &'static str = "Pack";
+
+    fn fetch(&mut self, id: &P) -> Result<PackReader, io::Error> {
+        self.find(id)
+    }
+
+    fn metadata(&mut self, id: &P) -> Result<Option<Metadata>, io::Error> {
+        self.find_metadata(id).map(Some)
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        let prefix = VirtualPath::new(prefix);
+        Ok(self
+            .entries
+            .iter()
+            .filter(|entry| in_dir(&entry.name, prefix.as_str()))
+            .map(|entry| entry.name.clone())
+            .collect())
+    }
+}
+
+enum Decoder {
+    None(Take<BufReader<File>>),
+    Deflate(DeflateDecoder<Take<BufReader<File>>>),
+}
+
+impl Read for Decoder {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        match *self {
+            Decoder::None(ref mut reader) => reader.read(buf),
+            Decoder::Deflate(ref mut reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Reader of pack entry.
+/// Checks size and checksum of the entry data when reaching the end.
+pub struct PackReader {
+    reader: CheckedReader<Decoder>,
+}
+
+impl Read for PackReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.reader.read(buf)
+    }
+}
+
+/// `PackWriter` builds pack files that can be read by `PackStore`.
+pub struct PackWriter<W> {
+    writer: W,
+    offset: u64,
+    entries: Vec<Entry>,
+    names: HashSet<String>,
+}
+
+impl<W> PackWriter<W>
+where
+    W: Write + Seek,
+{
+    /// Start writing pack.
+    pub fn new(mut writer: W) -> Result<Self, io::Error> {
+        writer.write_all(&[0; HEADER_SIZE as usize])?;
+        Ok(PackWriter {
+            writer,
+            offset: HEADER_SIZE,
+            entries: Vec::new(),
+            names: HashSet::new(),
+        })
+    }
+
+    /// Add entry to the pack.
+    pub fn add(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        compression: PackCompression,
+    ) -> Result<(), io::Error> {
+        let name = VirtualPath::new(name).as_str().to_owned();
+        if self.names.contains(&name) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Entry {:?} is already in the pack", name),
+            ));
+        }
+        if self.entries.len() >= u32::MAX as usize {
+            return Err(io::Error::other(
+                "Pack can't contain more than u32::MAX entries",
+            ));
+        }
+
+        let mut crc = Crc::new();
+        crc.update(data);
+
+        let stored_size = match compression {
+            PackCompression::None => {
+                self.writer.write_all(data)?;
+                data.len() as u64
+            }
+            PackCompression::Deflate => {
+                let compressed = {
+                    let mut encoder = DeflateEncoder::new(Vec::new(), Default::default());
+                    encoder.write_all(data)?;
+                    encoder.finish()?
+                };
+                self.writer.write_all(&compressed)?;
+                compressed.len() as u64
+            }
+        };
+
+        debug!("Entry {:?} added to the pack", name);
+        self.names.insert(name.clone());
+        self.entries.push(Entry {
+            name,
+            offset: self.offset,
+            size: data.len() as u64,
+            stored_size,
+            compression,
+            crc: crc.sum(),
+        });
+        self.offset += stored_size;
+        Ok(())
+    }
+
+    /// Add file to the pack.
+    pub fn add_file<P>(
+        &mut self,
+        name: &str,
+        path: P,
+        compression: PackCompression,
+    ) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+        self.add(name, &data, compression)
+    }
+
+    /// Add all files from directory recursively.
+    /// Entries are named by paths relative to the directory.
+    pub fn add_dir<P>(&mut self, root: P, compression: PackCompression) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let root = root.as_ref();
+        let mut files = Vec::new();
+        walk(root, &mut files)?;
+        files.sort();
+        for file in files {
+            let name = VirtualPath::from_path(file.strip_prefix(root).unwrap());
+            if self.names.contains(name.as_str()) {
+                continue;
+            }
+            self.add_file(name.as_str(), &file, compression)?;
+        }
+        Ok(())
+    }
+
+    /// Add all files from `FsStore` roots.
+    /// Files from earlier roots take precedence as they do for `FsStore`.
+    #[cfg(feature = "fs")]
+    pub fn add_fs_store(
+        &mut self,
+        store: &FsStore,
+        compression: PackCompression,
+    ) -> Result<(), io::Error> {
+        for root in store.roots() {
+            self.add_dir(root, compression)?;
+        }
+        Ok(())
+    }
+
+    /// Write index and finish the pack.
+    /// Returns underlying writer.
+    pub fn finish(mut self) -> Result<W, io::Error> {
+        self.entries
+            .sort_by(|left, right| left.name.as_str().cmp(right.name.as_str()));
+
+        for entry in &self.entries {
+            write_entry(&mut self.writer, entry)?;
+        }
+
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(MAGIC)?;
+        write_u32(&mut self.writer, VERSION)?;
+        write_u64(&mut self.writer, self.offset)?;
+        // `add` keeps the count within `u32`.
+        write_u32(&mut self.writer, self.entries.len() as u32)?;
+        self.writer.flush()?;
+
+        info!("Pack with {} entries written", self.entries.len());
+        Ok(self.writer)
+    }
+}
+
+fn read_entry<R>(reader: &mut R) -> Result<Entry, io::Error>
+where
+    R: Read,
+{
+    let name_size = read_u16(reader)?;
+    let mut name = vec![0; name_size as usize];
+    reader.read_exact(&mut name)?;
+    let name = String::from_utf8(name).map_err(|_| invalid("Entry name is not UTF-8"))?;
+    let offset = read_u64(reader)?;
+    let size = read_u64(reader)?;
+    let stored_size = read_u64(reader)?;
+    let mut compression = [0];
+    reader.read_exact(&mut compression)?;
+    let compression = match compression[0] {
+        0 => PackCompression::None,
+        1 => PackCompression::Deflate,
+        _ => return Err(invalid("Unknown entry compression")),
+    };
+    let crc = read_u32(reader)?;
+    Ok(Entry {
+        name,
+        offset,
+        size,
+        stored_size,
+        compression,
+        crc,
+    })
+}
+
+fn write_entry<W>(writer: &mut W, entry: &Entry) -> Result<(), io::Error>
+where
+    W: Write,
+{
+    let name = entry.name.as_bytes();
+    if name.len() > 0xFFFF {
+        return Err(invalid("Entry name is too long"));
+    }
+    write_u16(writer, name.len() as u16)?;
+    writer.write_all(name)?;
+    write_u64(writer, entry.offset)?;
+    write_u64(writer, entry.size)?;
+    write_u64(writer, entry.stored_size)?;
+    writer.write_all(&[match entry.compression {
+        PackCompression::None => 0,
+        PackCompression::Deflate => 1,
+    }])?;
+    write_u32(writer, entry.crc)
+}
+
+fn read_u16<R>(reader: &mut R) -> Result<u16, io::Error>
+where
+    R: Read,
+{
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0] as u16 | (bytes[1] as u16) << 8)
+}
+
+fn read_u32<R>(reader: &mut R) -> Result<u32, io::Error>
+where
+    R: Read,
+{
+    Ok(read_u16(reader)? as u32 | (read_u16(reader)? as u32) << 16)
+}
+
+fn read_u64<R>(reader: &mut R) -> Result<u64, io::Error>
+where
+    R: Read,
+{
+    Ok(read_u32(reader)? as u64 | (read_u32(reader)? as u64) << 32)
+}
+
+fn write_u16<W>(writer: &mut W, value: u16) -> Result<(), io::Error>
+where
+    W: Write,
+{
+    writer.write_all(&[value as u8, (value >> 8) as u8])
+}
+
+fn write_u32<W>(writer: &mut W, value: u32) -> Result<(), io::Error>
+where
+    W: Write,
+{
+    write_u16(writer, value as u16)?;
+    write_u16(writer, (value >> 16) as u16)
+}
+
+fn write_u64<W>(writer: &mut W, value: u64) -> Result<(), io::Error>
+where
+    W: Write,
+{
+    write_u32(writer, value as u32)?;
+    write_u32(writer, (value >> 32) as u32)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
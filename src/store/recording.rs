@@ -0,0 +1,153 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use path::VirtualPath;
+use store::{Bytes, Metadata, SeekRead, Store};
+
+/// Way asset data was requested from a store.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FetchKind {
+    /// `Store::fetch`.
+    Read,
+
+    /// `Store::fetch_seekable`.
+    Seek,
+
+    /// `Store::fetch_bytes`.
+    Bytes,
+}
+
+/// Fetch recorded by `RecordingStore`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fetch {
+    id: String,
+    kind: FetchKind,
+    ok: bool,
+}
+
+impl Fetch {
+    /// Normalized id of requested asset.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Way the data was requested.
+    pub fn kind(&self) -> FetchKind {
+        self.kind
+    }
+
+    /// Check if store returned no error.
+    pub fn is_ok(&self) -> bool {
+        self.ok
+    }
+}
+
+/// Log of fetches shared by `RecordingStore` and its observers.
+#[derive(Clone, Debug, Default)]
+pub struct FetchLog {
+    fetches: Arc<Mutex<Vec<Fetch>>>,
+}
+
+impl FetchLog {
+    /// Create new empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get fetches recorded so far in order they were made.
+    pub fn fetches(&self) -> Vec<Fetch> {
+        self.fetches.lock().unwrap().clone()
+    }
+
+    /// Get ids of assets fetched so far in order they were requested.
+    pub fn ids(&self) -> Vec<String> {
+        self.fetches
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|fetch| fetch.id.clone())
+            .collect()
+    }
+
+    /// Forget recorded fetches.
+    pub fn clear(&self) {
+        self.fetches.lock().unwrap().clear();
+    }
+
+    fn record(&self, id: &Path, kind: FetchKind, ok: bool) {
+        self.fetches.lock().unwrap().push(Fetch {
+            id: VirtualPath::from_path(id).as_str().to_owned(),
+            kind,
+            ok,
+        });
+    }
+}
+
+/// `RecordingStore` wraps store and records every fetch into `FetchLog`.
+/// Intended for assertions in tests.
+#[derive(Debug)]
+pub struct RecordingStore<S> {
+    store: S,
+    log: FetchLog,
+}
+
+impl<S> RecordingStore<S> {
+    /// Wrap store.
+    pub fn new(store: S) -> Self {
+        RecordingStore {
+            store,
+            log: FetchLog::new(),
+        }
+    }
+
+    /// Get log of fetches.
+    /// Returned log is shared with the store and observes future fetches.
+    pub fn log(&self) -> FetchLog {
+        self.log.clone()
+    }
+
+    /// Unwrap store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+impl<I, S> Store<I> for RecordingStore<S>
+where
+    I: AsRef<Path> + ?Sized,
+    S: Store<I>,
+{
+    type Error = S::Error;
+    type Reader = S::Reader;
+
+    const KIND: &'static str = S::KIND;
+
+    fn fetch(&mut self, id: &I) -> Result<S::Reader, S::Error> {
+        let result = self.store.fetch(id);
+        self.log
+            .record(id.as_ref(), FetchKind::Read, result.is_ok());
+        result
+    }
+
+    fn fetch_seekable(&mut self, id: &I) -> Result<Option<Box<SeekRead>>, S::Error> {
+        let result = self.store.fetch_seekable(id);
+        self.log
+            .record(id.as_ref(), FetchKind::Seek, result.is_ok());
+        result
+    }
+
+    fn fetch_bytes(&mut self, id: &I) -> Result<Option<Bytes>, S::Error> {
+        let result = self.store.fetch_bytes(id);
+        self.log
+            .record(id.as_ref(), FetchKind::Bytes, result.is_ok());
+        result
+    }
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, S::Error> {
+        self.store.metadata(id)
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, S::Error> {
+        self.store.list(prefix)
+    }
+}
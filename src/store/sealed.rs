@@ -0,0 +1,248 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs::{create_dir_all, File};
+use std::io::{self, Cursor, Read, Write};
+use std::path::Path;
+
+use blake3;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use failure::Error;
+
+use path::VirtualPath;
+use store::walk::walk;
+use store::{Bytes, Metadata, SeekRead, Store};
+
+const MAGIC: &[u8; 4] = b"ASEL";
+const VERSION: u8 = 1;
+const NONCE_SIZE: usize = 24;
+const HEADER_SIZE: usize = 5 + NONCE_SIZE;
+const TAG_SIZE: usize = 16;
+const NONCE_CONTEXT: &str = "asset sealed store 2018-06-01 nonce";
+
+/// Error of opening sealed asset that was tampered with or sealed with another key or id.
+/// Returned wrapped into `io::Error` of `InvalidData` kind.
+#[derive(Debug)]
+pub struct IntegrityError {
+    id: String,
+}
+
+impl IntegrityError {
+    /// Get id of the asset that failed integrity check.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Asset {:?} failed integrity check", self.id)
+    }
+}
+
+impl StdError for IntegrityError {
+    fn description(&self) -> &str {
+        "Asset failed integrity check"
+    }
+}
+
+/// Symmetric key used to seal and open assets.
+#[derive(Clone)]
+pub struct SealKey([u8; 32]);
+
+impl SealKey {
+    /// Create key from raw bytes.
+    pub fn new(key: [u8; 32]) -> Self {
+        SealKey(key)
+    }
+
+    /// Seal asset data.
+    /// Data is encrypted and authenticated together with asset id,
+    /// so sealed data can't be used for other asset.
+    /// Nonce is derived from key, id and data, so sealing is deterministic.
+    pub fn seal(&self, id: &str, data: &[u8]) -> Vec<u8> {
+        let id = VirtualPath::new(id);
+
+        let mut hasher = blake3::Hasher::new_keyed(&blake3::derive_key(NONCE_CONTEXT, &self.0));
+        hasher.update(id.as_str().as_bytes());
+        hasher.update(&[0]);
+        hasher.update(data);
+        let mut nonce = [0; NONCE_SIZE];
+        nonce.copy_from_slice(&hasher.finalize().as_bytes()[..NONCE_SIZE]);
+
+        let mut sealed = Vec::with_capacity(HEADER_SIZE + data.len() + TAG_SIZE);
+        sealed.extend_from_slice(MAGIC);
+        sealed.push(VERSION);
+        sealed.extend_from_slice(&nonce);
+
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: data,
+                    aad: &aad(&id),
+                },
+            )
+            .expect("Encryption can't fail");
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    /// Open sealed asset data.
+    pub fn open(&self, id: &str, sealed: &[u8]) -> Result<Vec<u8>, io::Error> {
+        let id = VirtualPath::new(id);
+        let integrity = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                IntegrityError {
+                    id: id.as_str().to_owned(),
+                },
+            )
+        };
+
+        if sealed.len() < HEADER_SIZE || &sealed[..4] != MAGIC || sealed[4] != VERSION {
+            return Err(integrity());
+        }
+
+        self.cipher()
+            .decrypt(
+                XNonce::from_slice(&sealed[5..HEADER_SIZE]),
+                Payload {
+                    msg: &sealed[HEADER_SIZE..],
+                    aad: &aad(&id),
+                },
+            )
+            .map_err(|_| integrity())
+    }
+
+    /// Seal all files from directory recursively into another directory.
+    /// Files keep relative paths and are sealed with ids `FsStore` would find them by.
+    pub fn seal_dir<P, Q>(&self, source: P, destination: Q) -> Result<(), io::Error>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let source = source.as_ref();
+        let destination = destination.as_ref();
+
+        let mut files = Vec::new();
+        walk(source, &mut files)?;
+        for file in files {
+            let relative = file.strip_prefix(source).unwrap();
+            let id = VirtualPath::from_path(relative);
+
+            let mut data = Vec::new();
+            File::open(&file)?.read_to_end(&mut data)?;
+
+            let target = destination.join(relative);
+            if let Some(parent) = target.parent() {
+                create_dir_all(parent)?;
+            }
+            File::create(target)?.write_all(&self.seal(id.as_str(), &data))?;
+            debug!("Asset {} sealed", id);
+        }
+        Ok(())
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(Key::from_slice(&self.0))
+    }
+}
+
+impl fmt::Debug for SealKey {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("SealKey(..)")
+    }
+}
+
+/// `SealedStore` wraps store with assets sealed by `SealKey`
+/// and opens them, verifying their integrity.
+#[derive(Debug)]
+pub struct SealedStore<S> {
+    store: S,
+    key: SealKey,
+}
+
+impl<S> SealedStore<S> {
+    /// Wrap store.
+    pub fn new(store: S, key: SealKey) -> Self {
+        SealedStore { store, key }
+    }
+
+    /// Find and open sealed asset.
+    pub fn find<I>(&mut self, id: &I) -> Result<Cursor<Vec<u8>>, Error>
+    where
+        I: AsRef<Path> + ?Sized,
+        S: Store<I>,
+        S::Error: Into<Error>,
+    {
+        let mut sealed = Vec::new();
+        self.store
+            .fetch(id)
+            .map_err(Into::into)?
+            .read_to_end(&mut sealed)?;
+        let data = self
+            .key
+            .open(VirtualPath::from_path(id.as_ref()).as_str(), &sealed)?;
+        Ok(Cursor::new(data))
+    }
+
+    /// Get metadata of sealed asset.
+    /// Size is computed from the sealed data size.
+    pub fn find_metadata<I>(&mut self, id: &I) -> Result<Option<Metadata>, Error>
+    where
+        I: AsRef<Path> + ?Sized,
+        S: Store<I>,
+        S::Error: Into<Error>,
+    {
+        let metadata = self.store.metadata(id).map_err(Into::into)?;
+        Ok(metadata.map(|metadata| {
+            let size = metadata.size().saturating_sub((HEADER_SIZE + TAG_SIZE) as u64);
+            match metadata.modified() {
+                Some(modified) => Metadata::new(size).with_modified(modified),
+                None => Metadata::new(size),
+            }
+        }))
+    }
+}
+
+impl<I, S> Store<I> for SealedStore<S>
+where
+    I: AsRef<Path> + ?Sized,
+    S: Store<I>,
+    S::Error: Into<Error>,
+{
+    type Error = Error;
+    type Reader = Cursor<Vec<u8>>;
+
+    const KIND: &'static str = "Sealed";
+
+    fn fetch(&mut self, id: &I) -> Result<Cursor<Vec<u8>>, Error> {
+        self.find(id)
+    }
+
+    fn fetch_seekable(&mut self, id: &I) -> Result<Option<Box<SeekRead>>, Error> {
+        Ok(Some(Box::new(self.find(id)?)))
+    }
+
+    fn fetch_bytes(&mut self, id: &I) -> Result<Option<Bytes>, Error> {
+        Ok(Some(Bytes::from(self.find(id)?.into_inner())))
+    }
+
+    fn metadata(&mut self, id: &I) -> Result<Option<Metadata>, Error> {
+        self.find_metadata(id)
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, Error> {
+        self.store.list(prefix).map_err(Into::into)
+    }
+}
+
+fn aad(id: &VirtualPath) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(5 + id.as_str().len());
+    aad.extend_from_slice(MAGIC);
+    aad.push(VERSION);
+    aad.extend_from_slice(id.as_str().as_bytes());
+    aad
+}
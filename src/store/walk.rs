@@ -0,0 +1,19 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Collect all files in directory recursively.
+/// Symlinks to directories are skipped so that cyclic links can't loop forever.
+pub fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), io::Error> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(&path, files)?;
+        } else if !(file_type.is_symlink() && path.is_dir()) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
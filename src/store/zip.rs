@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Take};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use flate2::read::DeflateDecoder;
+
+use path::VirtualPath;
+use store::checked::CheckedReader;
+use store::{in_dir, Metadata, Store};
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const MAX_COMMENT_SIZE: usize = 0xFFFF;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Method {
+    Stored,
+    Deflated,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    archive: usize,
+    method: Method,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    modified: Option<SystemTime>,
+    header_offset: u64,
+}
+
+/// `ZipStore` fetches data from zip archives.
+/// Archive's central directory is read once when archive is added.
+/// Only stored and deflated entries are supported.
+#[derive(Debug)]
+pub struct ZipStore {
+    archives: Vec<PathBuf>,
+    entries: HashMap<String, Entry>,
+}
+
+impl ZipStore {
+    /// Create new `ZipStore` without archives.
+    pub fn new() -> Self {
+        ZipStore {
+            archives: Vec::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Add archive and read its index.
+    /// Entries of previously added archives take precedence.
+    pub fn add_archive<P>(&mut self, path: P) -> Result<(), io::Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let archive = self.archives.len();
+        let entries = read_index(&mut BufReader::new(File::open(&path)?), archive)?;
+        info!("Zip archive {:?} with {} entries added", path, entries.len());
+        for (name, entry) in entries {
+            self.entries.entry(name).or_insert(entry);
+        }
+        self.archives.push(path);
+        Ok(())
+    }
+
+    /// Add archive and read its index.
+    pub fn with_archive<P>(mut self, path: P) -> Result<Self, io::Error>
+    where
+        P: Into<PathBuf>,
+    {
+        self.add_archive(path)?;
+        Ok(self)
+    }
+
+    /// Find entry by name and get its metadata.
+    /// Modification time is read from the entry assuming it's in UTC.
+    pub fn find_metadata<P>(&self, path: P) -> Result<Metadata, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let name = VirtualPath::from_path(path.as_ref());
+        let entry = self
+            .entries
+            .get(name.as_str())
+            .ok_or(io::ErrorKind::NotFound)?;
+
+        let metadata = Metadata::new(entry.size);
+        Ok(match entry.modified {
+            Some(modified) => metadata.with_modified(modified),
+            None => metadata,
+        })
+    }
+
+    /// Find entry by name.
+    pub fn find<P>(&self, path: P) -> Result<ZipReader, io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        let name = VirtualPath::from_path(path.as_ref());
+        let entry = self
+            .entries
+            .get(name.as_str())
+            .ok_or(io::ErrorKind::NotFound)?;
+
+        let mut file = BufReader::new(File::open(&self.archives[entry.archive])?);
+        file.seek(SeekFrom::Start(entry.header_offset))?;
+        let mut header = [0; 30];
+        file.read_exact(&mut header)?;
+        if u32_at(&header, 0) != LOCAL_HEADER_SIGNATURE {
+            return Err(invalid("Invalid local file header signature"));
+        }
+        let skip = u16_at(&header, 26) as i64 + u16_at(&header, 28) as i64;
+        file.seek(SeekFrom::Current(skip))?;
+
+        let data = file.take(entry.compressed_size);
+        let reader = match entry.method {
+            Method::Stored => Decoder::Stored(data),
+            Method::Deflated => Decoder::Deflated(DeflateDecoder::new(data)),
+        };
+
+        Ok(ZipReader {
+            reader: CheckedReader::new(reader, entry.crc, entry.size),
+        })
+    }
+}
+
+impl<P> Store<P> for ZipStore
+where
+    P: AsRef<Path> + ?Sized,
+{
+    type Error = io::Error;
+    type Reader = ZipReader;
+
+    const KIND: &'static str = "Zip";
+
+    fn fetch(&mut self, id: &P) -> Result<ZipReader, io::Error> {
+        self.find(id)
+    }
+
+    fn metadata(&mut self, id: &P) -> Result<Option<Metadata>, io::Error> {
+        self.find_metadata(id).map(Some)
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        let prefix = VirtualPath::new(prefix);
+        let mut ids: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|id| in_dir(id, prefix.as_str()))
+            .cloned()
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+enum Decoder {
+    Stored(Take<BufReader<File>>),
+    Deflated(DeflateDecoder<Take<BufReader<File>>>),
+}
+
+impl Read for Decoder {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        match *self {
+            Decoder::Stored(ref mut reader) => reader.read(buf),
+            Decoder::Deflated(ref mut reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Reader of zip archive entry.
+/// Checks size and checksum of the entry data when reaching the end.
+pub struct ZipReader {
+    reader: CheckedReader<Decoder>,
+}
+
+impl Read for ZipReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.reader.read(buf)
+    }
+}
+
+fn read_index<R>(reader: &mut R, archive: usize) -> Result<HashMap<String, Entry>, io::Error>
+where
+    R: Read + Seek,
+{
+    let file_size = reader.seek(SeekFrom::End(0))?;
+    let tail_size = file_size.min((END_OF_CENTRAL_DIRECTORY_SIZE + MAX_COMMENT_SIZE) as u64);
+    reader.seek(SeekFrom::Start(file_size - tail_size))?;
+    let mut tail = Vec::new();
+    reader.read_to_end(&mut tail)?;
+
+    let end = (0..tail.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_SIZE - 1))
+        .rev()
+        .find(|&offset| u32_at(&tail, offset) == END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+        .ok_or_else(|| invalid("End of central directory is not found"))?;
+    let end_offset = file_size - tail_size + end as u64;
+    let end = &tail[end..];
+
+    let count = u16_at(end, 10) as usize;
+    let directory_size = u32_at(end, 12);
+    let directory_offset = u32_at(end, 16);
+    if count == 0xFFFF || directory_size == 0xFFFF_FFFF || directory_offset == 0xFFFF_FFFF {
+        return Err(unsupported("Zip64 archives are not supported"));
+    }
+
+    // Sizes come from untrusted data and are checked before allocating.
+    if directory_offset as u64 + directory_size as u64 > end_offset {
+        return Err(invalid("Central directory is out of archive bounds"));
+    }
+
+    reader.seek(SeekFrom::Start(directory_offset as u64))?;
+    let mut directory = vec![0; directory_size as usize];
+    reader.read_exact(&mut directory)?;
+
+    let mut entries = HashMap::with_capacity(count);
+    let mut offset = 0;
+    for _ in 0..count {
+        let header = directory
+            .get(offset..offset + 46)
+            .ok_or_else(|| invalid("Central directory is truncated"))?;
+        if u32_at(header, 0) != CENTRAL_HEADER_SIGNATURE {
+            return Err(invalid("Invalid central directory header signature"));
+        }
+        let flags = u16_at(header, 8);
+        let method = u16_at(header, 10);
+        let modified = dos_time(u16_at(header, 14), u16_at(header, 12));
+        let crc = u32_at(header, 16);
+        let compressed_size = u32_at(header, 20);
+        let size = u32_at(header, 24);
+        let name_size = u16_at(header, 28) as usize;
+        let extra_size = u16_at(header, 30) as usize;
+        let comment_size = u16_at(header, 32) as usize;
+        let header_offset = u32_at(header, 42);
+
+        let name = directory
+            .get(offset + 46..offset + 46 + name_size)
+            .ok_or_else(|| invalid("Central directory is truncated"))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        offset += 46 + name_size + extra_size + comment_size;
+
+        if name.ends_with('/') {
+            continue;
+        }
+
+        if compressed_size == 0xFFFF_FFFF || size == 0xFFFF_FFFF || header_offset == 0xFFFF_FFFF {
+            return Err(unsupported("Zip64 archives are not supported"));
+        }
+
+        if flags & 1 != 0 {
+            warn!("Encrypted zip entry {:?} skipped", name);
+            continue;
+        }
+
+        let method = match method {
+            0 => Method::Stored,
+            8 => Method::Deflated,
+            method => {
+                warn!("Zip entry {:?} with compression method {} skipped", name, method);
+                continue;
+            }
+        };
+
+        entries.insert(
+            VirtualPath::new(&name).as_str().to_owned(),
+            Entry {
+                archive,
+                method,
+                crc,
+                compressed_size: compressed_size as u64,
+                size: size as u64,
+                modified,
+                header_offset: header_offset as u64,
+            },
+        );
+    }
+
+    Ok(entries)
+}
+
+/// Convert MS-DOS date and time to `SystemTime` assuming UTC.
+fn dos_time(date: u16, time: u16) -> Option<SystemTime> {
+    let year = 1980 + (date >> 9) as u64;
+    let month = ((date >> 5) & 0xF) as u64;
+    let day = (date & 0x1F) as u64;
+    if month < 1 || month > 12 || day < 1 {
+        return None;
+    }
+
+    // Days since 1970-01-01 counting years from March.
+    let (year, month) = if month > 2 {
+        (year, month - 3)
+    } else {
+        (year - 1, month + 9)
+    };
+    let days = year * 365 + year / 4 - year / 100 + year / 400 + (153 * month + 2) / 5 + day - 1
+        - 719_468;
+
+    let seconds = (time >> 11) as u64 * 3600 + ((time >> 5) & 0x3F) as u64 * 60
+        + (time & 0x1F) as u64 * 2;
+    Some(UNIX_EPOCH + Duration::from_secs(days * 86_400 + seconds))
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    bytes[offset] as u16 | (bytes[offset + 1] as u16) << 8
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u16_at(bytes, offset) as u32 | (u16_at(bytes, offset + 2) as u32) << 16
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn unsupported(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message)
+}
@@ -0,0 +1,10 @@
+&[
+    (
+        "LICENSE-MIT",
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/LICENSE-MIT")),
+    ),
+    (
+        "tests/mod.rs",
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/mod.rs")),
+    ),
+]
@@ -1,6 +1,7 @@
-use asset::{Asset, AssetLoader};
+use asset::{Asset, AssetLoader, AssetLoaderKind, BytesAssetLoader, SeekAssetLoader};
 use ron;
-use std::io::{Error, Read};
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+use store::Bytes;
 
 #[derive(Debug, PartialEq, Eq, Deserialize)]
 struct Foo {
@@ -14,6 +15,10 @@ impl Asset for Foo {
 
 struct FooLoader;
 
+impl AssetLoaderKind for FooLoader {
+    const KIND: &'static str = "FooLoader";
+}
+
 impl AssetLoader<Foo, ()> for FooLoader {
     type Error = ron::de::Error;
 
@@ -53,6 +58,11 @@ DEALINGS IN THE SOFTWARE.
 ";
 
 pub struct StringLoader;
+
+impl AssetLoaderKind for StringLoader {
+    const KIND: &'static str = "StringLoader";
+}
+
 impl AssetLoader<String, ()> for StringLoader {
     type Error = Error;
 
@@ -66,6 +76,25 @@ impl AssetLoader<String, ()> for StringLoader {
     }
 }
 
+impl SeekAssetLoader<String, ()> for StringLoader {
+    fn load_seekable<R>(&mut self, _: (), mut reader: R) -> Result<String, Error>
+    where
+        R: Read + Seek,
+    {
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        let mut text = String::with_capacity(len as usize);
+        reader.read_to_string(&mut text)?;
+        Ok(text)
+    }
+}
+
+impl BytesAssetLoader<String, ()> for StringLoader {
+    fn load_bytes(&mut self, _: (), bytes: Bytes) -> Result<String, Error> {
+        String::from_utf8(bytes.to_vec()).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
 impl Asset for String {
     const KIND: &'static str = "String";
 
@@ -83,8 +112,18 @@ fn asset_loader() {
 #[cfg(feature = "fs")]
 #[test]
 fn filesystem_store() {
+    use std::fs::{create_dir_all, File};
+    use std::io::Write;
     use store::{FsStore, Store};
-    let mut fs = FsStore::new().with_path(env!("CARGO_MANIFEST_DIR"));
+
+    let dir = ::std::env::temp_dir().join("asset-filesystem-store-test");
+    create_dir_all(&dir).unwrap();
+    File::create(dir.join("LICENSE-MIT"))
+        .unwrap()
+        .write_all(LICENSE_MIT.as_bytes())
+        .unwrap();
+
+    let mut fs = FsStore::new().with_path(dir);
     assert_eq!(
         String::from(LICENSE_MIT),
         fs.fetch("LICENSE-MIT")
@@ -93,14 +132,13 @@ fn filesystem_store() {
     );
 }
 
-#[cfg(feature = "fs")]
 #[test]
 fn asset_manager() {
-    use store::FsStore;
+    use store::MemoryStore;
     use Assets;
 
     let mut manager = Assets::new()
-        .with_store(FsStore::new().with_path(env!("CARGO_MANIFEST_DIR")))
+        .with_store(MemoryStore::new().with("LICENSE-MIT", LICENSE_MIT.as_bytes()))
         .with_loader(StringLoader);
 
     assert_eq!(
@@ -111,3 +149,1302 @@ fn asset_manager() {
     fn send_sync_static<T: Send + Sync + 'static>(_: &T) {}
     send_sync_static(&manager);
 }
+
+#[test]
+fn swap_handle() {
+    use handle::{Handle, SwapHandle};
+
+    let swap = SwapHandle::new(Handle::new(1u32));
+    assert_eq!(swap.version(), 0);
+    assert_eq!(*swap.get(), 1);
+
+    let old = swap.replace(2);
+    assert_eq!(*old, 1);
+    assert_eq!(*swap.get(), 2);
+    assert_eq!(swap.get_versioned().1, 1);
+
+    let other = swap.clone();
+    other.swap(Handle::new(3));
+    assert_eq!(swap.version(), 2);
+    assert_eq!(*swap.get(), 3);
+}
+
+#[test]
+fn handle_identity() {
+    use handle::Handle;
+    use std::collections::HashSet;
+
+    let a = Handle::new(String::from("a"));
+    let b = Handle::new(String::from("a"));
+
+    assert_eq!(a, a.clone());
+    assert_ne!(a, b);
+    assert_eq!(a.id(), a.clone().id());
+
+    let set: HashSet<_> = vec![a.clone(), b.clone(), a.clone()].into_iter().collect();
+    assert_eq!(set.len(), 2);
+
+    assert!(format!("{:?}", a).contains("String"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn handle_serde() {
+    use handle::{with_context, Handle};
+    use std::path::PathBuf;
+    use store::MemoryStore;
+    use Assets;
+
+    #[derive(Serialize, Deserialize)]
+    struct Scene {
+        license: Handle<String>,
+    }
+
+    let mut manager = Assets::<PathBuf>::new()
+        .with_store(MemoryStore::new().with("LICENSE-MIT".into(), LICENSE_MIT.as_bytes()))
+        .with_loader(StringLoader)
+        .with_format::<String, _>(());
+
+    let scene: Scene = with_context(&mut manager, || {
+        ron::de::from_str("(license: \"LICENSE-MIT\")").unwrap()
+    });
+    assert_eq!(&String::from(LICENSE_MIT), &*scene.license);
+
+    let license = manager.load::<String, _>("LICENSE-MIT".into(), ()).unwrap();
+    assert_eq!(scene.license, license);
+
+    let text = with_context(&mut manager, || ron::ser::to_string(&scene).unwrap());
+    let copy: Scene = with_context(&mut manager, || ron::de::from_str(&text).unwrap());
+    assert_eq!(scene.license, copy.license);
+
+    // Mapping to the whole asset keeps its id, parts of values have none.
+    let whole = Scene {
+        license: license.map(|license| license),
+    };
+    assert_eq!(
+        with_context(&mut manager, || ron::ser::to_string(&whole).unwrap()),
+        text
+    );
+    let pair = Handle::new((String::from(LICENSE_MIT), String::new()));
+    let part = Scene {
+        license: pair.map(|pair| &pair.0),
+    };
+    let err = with_context(&mut manager, || ron::ser::to_string(&part).unwrap_err());
+    assert!(err.to_string().contains("unknown to the handle context"));
+
+    let debug = with_context(&mut manager, || format!("{:?}", scene.license));
+    assert!(debug.contains("\"LICENSE-MIT\""));
+    assert!(!format!("{:?}", scene.license).contains("LICENSE-MIT"));
+}
+
+#[cfg(all(feature = "serde", feature = "ron"))]
+#[test]
+fn handle_serde_nested() {
+    use handle::Handle;
+    use std::path::PathBuf;
+    use store::MemoryStore;
+    use {Assets, RonFormat, SerdeLoader};
+
+    #[derive(Deserialize)]
+    struct Part {
+        name: String,
+        whole: Option<Handle<Whole>>,
+    }
+
+    impl Asset for Part {
+        const KIND: &'static str = "Part";
+        type Loader = PartLoader;
+    }
+
+    struct PartLoader;
+
+    impl AssetLoaderKind for PartLoader {
+        const KIND: &'static str = "PartLoader";
+    }
+
+    impl AssetLoader<Part, ()> for PartLoader {
+        type Error = ron::de::Error;
+
+        fn load<R>(&mut self, _: (), reader: R) -> Result<Part, ron::de::Error>
+        where
+            R: Read,
+        {
+            ron::de::from_reader(reader)
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Whole {
+        part: Handle<Part>,
+    }
+
+    impl Asset for Whole {
+        const KIND: &'static str = "Whole";
+        type Loader = SerdeLoader;
+    }
+
+    #[derive(Deserialize)]
+    struct Scene {
+        whole: Handle<Whole>,
+    }
+
+    impl Asset for Scene {
+        const KIND: &'static str = "Scene";
+        type Loader = SerdeLoader;
+    }
+
+    let store = MemoryStore::new()
+        .with("whole.ron".into(), &b"(part: \"part.ron\")"[..])
+        .with("part.ron".into(), &b"(name: \"wheel\", whole: None)"[..])
+        .with("scene.ron".into(), &b"(whole: \"other.ron\")"[..])
+        .with("other.ron".into(), &b"(part: \"part.ron\")"[..])
+        .with("loop.ron".into(), &b"(part: \"back.ron\")"[..])
+        .with("back.ron".into(), &b"(name: \"back\", whole: Some(\"loop.ron\"))"[..]);
+    let mut manager = Assets::<PathBuf>::new()
+        .with_store(store)
+        .with_loader(SerdeLoader)
+        .with_loader(PartLoader)
+        .with_format::<Part, _>(())
+        .with_format::<Whole, _>(RonFormat);
+
+    let whole = manager
+        .load::<Whole, _>("whole.ron".into(), RonFormat)
+        .unwrap();
+    assert_eq!(whole.part.name, "wheel");
+
+    let part = manager.load::<Part, _>("part.ron".into(), ()).unwrap();
+    assert_eq!(whole.part, part);
+
+    let messages = |err: ::failure::Error| {
+        err.iter_chain()
+            .map(|cause| cause.to_string())
+            .collect::<Vec<_>>()
+            .join(": ")
+    };
+
+    // Same loader can't be used while it loads the referencing asset.
+    let err = manager
+        .load::<Scene, _>("scene.ron".into(), RonFormat)
+        .err()
+        .unwrap();
+    assert!(messages(err).contains("busy"));
+
+    // Assets referencing each other fail instead of recursing forever.
+    let err = manager
+        .load::<Whole, _>("loop.ron".into(), RonFormat)
+        .err()
+        .unwrap();
+    assert!(messages(err).contains("references itself"));
+    assert!(manager.load::<Part, _>("back.ron".into(), ()).is_err());
+}
+
+#[test]
+fn handle_map() {
+    use handle::Handle;
+
+    let pair = Handle::new((String::from("first"), String::from("second")));
+    let second = pair.map(|pair| &pair.1);
+    drop(pair);
+    assert_eq!(&*second, "second");
+    assert_eq!(second, second.map(|second| second));
+    assert!(second.try_map(|_| None::<&u32>).is_none());
+
+    // Only mapping requires `'static` values.
+    let text = String::from("borrowed");
+    let borrowed = Handle::new(text.as_str());
+    assert_eq!(*borrowed.clone(), "borrowed");
+}
+
+#[test]
+fn asset_id() {
+    use id::AssetId;
+    use store::MemoryStore;
+    use Assets;
+
+    let id = AssetId::new("LICENSE-MIT");
+    assert_eq!(id, AssetId::from(String::from("LICENSE-MIT")));
+    assert_ne!(id, AssetId::new("LICENSE-APACHE"));
+    assert_eq!(id.as_str(), "LICENSE-MIT");
+    assert_eq!(AssetId::new("./LICENSE-MIT"), id);
+    assert!(AssetId::new("LICENSE-APACHE") < id);
+
+    let mut manager = Assets::<AssetId>::new()
+        .with_store(MemoryStore::new().with(id, LICENSE_MIT.as_bytes()))
+        .with_loader(StringLoader);
+
+    let license = manager.load::<String, _>(id, ()).unwrap();
+    assert_eq!(license, manager.load::<String, _>(id, ()).unwrap());
+}
+
+#[test]
+fn virtual_path() {
+    use id::AssetId;
+    use path::{Case, VirtualPath};
+
+    assert_eq!(VirtualPath::new("textures/../a.png").as_str(), "a.png");
+    assert_eq!(VirtualPath::new("./a.png").as_str(), "a.png");
+    assert_eq!(VirtualPath::new("/../a.png").as_str(), "a.png");
+    assert_eq!(VirtualPath::new("textures\\b.png").as_str(), "textures/b.png");
+    assert_eq!(
+        VirtualPath::with_case("Textures/A.png", Case::Insensitive).as_str(),
+        "textures/a.png"
+    );
+
+    let path = VirtualPath::new("UI://menu/./button.png");
+    assert_eq!(path.scheme(), Some("ui"));
+    assert_eq!(path.path(), "menu/button.png");
+    assert_eq!(path.as_str(), "ui://menu/button.png");
+
+    assert_eq!(AssetId::new("./a/b/../c"), AssetId::new("a/c"));
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn filesystem_store_strict() {
+    use std::io::ErrorKind;
+    use store::{ForbiddenPath, FsStore, Store};
+
+    let mut fs = FsStore::new()
+        .with_path(concat!(env!("CARGO_MANIFEST_DIR"), "/src"))
+        .with_strict(true);
+
+    assert!(fs.fetch("lib.rs").is_ok());
+    assert_eq!(
+        fs.fetch("missing.rs").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+
+    for path in &[
+        "../LICENSE-MIT",
+        "store/../../LICENSE-MIT",
+        "/etc/passwd",
+        "..",
+        "a/..",
+        "/",
+    ] {
+        let err = fs.fetch(path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert!(err.get_ref().unwrap().is::<ForbiddenPath>());
+    }
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn zip_store() {
+    use flate2::write::DeflateEncoder;
+    use flate2::{Compression, Crc};
+    use std::fs::File;
+    use std::io::{ErrorKind, Write};
+    use std::time::{Duration, UNIX_EPOCH};
+    use store::{Store, ZipStore};
+
+    fn push_u16(bytes: &mut Vec<u8>, value: u16) {
+        bytes.extend_from_slice(&[value as u8, (value >> 8) as u8]);
+    }
+
+    fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+        push_u16(bytes, value as u16);
+        push_u16(bytes, (value >> 16) as u16);
+    }
+
+    let files: &[(&str, &[u8], bool)] = &[
+        ("stored.txt", b"stored data", false),
+        ("dir/deflated.txt", LICENSE_MIT.as_bytes(), true),
+    ];
+
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for &(name, data, deflate) in files {
+        let mut crc = Crc::new();
+        crc.update(data);
+        let compressed = if deflate {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        } else {
+            data.to_vec()
+        };
+
+        let mut header = Vec::new();
+        push_u16(&mut header, 20);
+        push_u16(&mut header, 0);
+        push_u16(&mut header, if deflate { 8 } else { 0 });
+        // Modified at 2018-06-01 12:30:00.
+        push_u16(&mut header, 12 << 11 | 30 << 5);
+        push_u16(&mut header, (2018 - 1980) << 9 | 6 << 5 | 1);
+        push_u32(&mut header, crc.sum());
+        push_u32(&mut header, compressed.len() as u32);
+        push_u32(&mut header, data.len() as u32);
+        push_u16(&mut header, name.len() as u16);
+
+        push_u32(&mut directory, 0x02014b50);
+        push_u16(&mut directory, 20);
+        directory.extend_from_slice(&header);
+        push_u16(&mut directory, 0);
+        push_u16(&mut directory, 0);
+        push_u16(&mut directory, 0);
+        push_u16(&mut directory, 0);
+        push_u32(&mut directory, 0);
+        push_u32(&mut directory, archive.len() as u32);
+        directory.extend_from_slice(name.as_bytes());
+
+        push_u32(&mut archive, 0x04034b50);
+        archive.extend_from_slice(&header);
+        push_u16(&mut archive, 0);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(&compressed);
+    }
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&directory);
+    push_u32(&mut archive, 0x06054b50);
+    push_u32(&mut archive, 0);
+    push_u16(&mut archive, files.len() as u16);
+    push_u16(&mut archive, files.len() as u16);
+    push_u32(&mut archive, directory.len() as u32);
+    push_u32(&mut archive, directory_offset);
+    push_u16(&mut archive, 0);
+
+    let path = ::std::env::temp_dir().join("asset-zip-store-test.zip");
+    File::create(&path).unwrap().write_all(&archive).unwrap();
+
+    let mut zip = ZipStore::new().with_archive(&path).unwrap();
+    assert_eq!(
+        "stored data",
+        zip.fetch("stored.txt")
+            .and_then(|r| StringLoader.load((), r))
+            .unwrap()
+    );
+    assert_eq!(
+        LICENSE_MIT,
+        zip.fetch("./dir/deflated.txt")
+            .and_then(|r| StringLoader.load((), r))
+            .unwrap()
+    );
+    assert_eq!(
+        zip.fetch("missing.txt").err().unwrap().kind(),
+        ErrorKind::NotFound
+    );
+
+    let metadata = zip.find_metadata("dir/deflated.txt").unwrap();
+    assert_eq!(metadata.size(), LICENSE_MIT.len() as u64);
+    assert_eq!(
+        metadata.modified(),
+        Some(UNIX_EPOCH + Duration::from_secs(1_527_856_200))
+    );
+
+    // Directory size claimed by the archive exceeds its length.
+    let size_at = archive.len() - 10;
+    archive[size_at..size_at + 4].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0x7F]);
+    File::create(&path).unwrap().write_all(&archive).unwrap();
+    assert_eq!(
+        ZipStore::new().with_archive(&path).err().unwrap().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+#[cfg(all(feature = "fs", feature = "pack"))]
+#[test]
+fn pack_store() {
+    use std::fs::File;
+    use std::io::ErrorKind;
+    use store::{FsStore, PackCompression, PackStore, PackWriter, Store};
+
+    let path = ::std::env::temp_dir().join("asset-pack-store-test.pack");
+    let fs = FsStore::new().with_path(concat!(env!("CARGO_MANIFEST_DIR"), "/src"));
+
+    let mut writer = PackWriter::new(File::create(&path).unwrap()).unwrap();
+    writer.add("LICENSE-MIT", LICENSE_MIT.as_bytes(), PackCompression::None).unwrap();
+    writer.add_fs_store(&fs, PackCompression::Deflate).unwrap();
+    assert_eq!(
+        writer
+            .add("./LICENSE-MIT", b"duplicate", PackCompression::None)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::AlreadyExists
+    );
+    writer.finish().unwrap();
+
+    let mut pack = PackStore::open(&path).unwrap();
+    assert_eq!(
+        LICENSE_MIT,
+        pack.fetch("LICENSE-MIT")
+            .and_then(|r| StringLoader.load((), r))
+            .unwrap()
+    );
+    assert_eq!(
+        include_str!("mod.rs"),
+        pack.fetch("tests/mod.rs")
+            .and_then(|r| StringLoader.load((), r))
+            .unwrap()
+    );
+    assert_eq!(
+        pack.fetch("missing.rs").err().unwrap().kind(),
+        ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn embedded_store() {
+    use std::fs::File;
+    use std::io::{ErrorKind, Read};
+    use store::{write_embedded, EmbeddedStore, Store};
+
+    static ENTRIES: &[(&str, &[u8])] = &[
+        ("LICENSE-MIT", LICENSE_MIT.as_bytes()),
+        ("tests/mod.rs", include_bytes!("mod.rs")),
+    ];
+
+    let mut store = EmbeddedStore::new(ENTRIES);
+    assert_eq!(
+        LICENSE_MIT,
+        store.fetch("LICENSE-MIT")
+            .and_then(|r| StringLoader.load((), r))
+            .unwrap()
+    );
+    assert_eq!(
+        include_str!("mod.rs"),
+        store.fetch("./tests/mod.rs")
+            .and_then(|r| StringLoader.load((), r))
+            .unwrap()
+    );
+    assert_eq!(
+        store.fetch("missing.rs").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+
+    let out = ::std::env::temp_dir().join("asset-embedded-store-test.rs");
+    write_embedded(concat!(env!("CARGO_MANIFEST_DIR"), "/src"), &out).unwrap();
+    let mut generated = String::new();
+    File::open(&out)
+        .unwrap()
+        .read_to_string(&mut generated)
+        .unwrap();
+    assert!(generated.contains("(\"tests/mod.rs\", include_bytes!("));
+
+    let mut store =
+        ::embedded_store!(path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/embedded.rs"));
+    assert_eq!(
+        include_str!("mod.rs"),
+        store.fetch("tests/mod.rs")
+            .and_then(|r| StringLoader.load((), r))
+            .unwrap()
+    );
+}
+
+#[test]
+#[should_panic(expected = "Entries must be sorted")]
+fn embedded_store_unsorted() {
+    use store::EmbeddedStore;
+
+    static ENTRIES: &[(&str, &[u8])] = &[("b", b"b"), ("a", b"a")];
+    EmbeddedStore::new(ENTRIES);
+}
+
+#[test]
+fn overlay_store() {
+    use std::path::PathBuf;
+    use store::{MemoryStore, OverlayStore, RecordingStore, Store};
+
+    let base = MemoryStore::<PathBuf>::new()
+        .with("a.txt".into(), &b"base a"[..])
+        .with("b.txt".into(), &b"base b"[..])
+        .with("c.txt".into(), &b"base c"[..])
+        .with("d.txt".into(), &b"base d"[..]);
+    let patch = MemoryStore::<PathBuf>::new().with("a.txt".into(), &b"patch a"[..]);
+    let mod_ = RecordingStore::new(
+        MemoryStore::<PathBuf>::new()
+            .with("a.txt".into(), &b"mod a"[..])
+            .with(".wh.b.txt".into(), &b""[..])
+            .with("d.txt".into(), &b"mod d"[..])
+            .with(".wh.d.txt".into(), &b""[..]),
+    );
+    let log = mod_.log();
+
+    let mut overlay = OverlayStore::new()
+        .with_layer("base", 0, base)
+        .with_layer("mod", 10, mod_)
+        .with_layer("patch", 0, patch);
+
+    let mut fetch = |id: &str| {
+        overlay
+            .fetch(id)
+            .ok()
+            .map(|r| StringLoader.load((), r).unwrap())
+    };
+    assert_eq!(fetch("a.txt"), Some("mod a".into()));
+    assert_eq!(fetch("b.txt"), None);
+    assert_eq!(fetch("c.txt"), Some("base c".into()));
+    // Whiteout hides only layers with lower priority.
+    assert_eq!(fetch("d.txt"), Some("mod d".into()));
+
+    assert_eq!(overlay.served_by("a.txt"), Some("mod"));
+    assert_eq!(overlay.served_by("b.txt"), None);
+    assert_eq!(overlay.served_by("c.txt"), Some("base"));
+    assert_eq!(overlay.served_by("./x/../a.txt"), Some("mod"));
+
+    // Data in memory is taken from the serving layer.
+    let bytes = Store::<str>::fetch_bytes(&mut overlay, "c.txt")
+        .unwrap()
+        .unwrap();
+    assert_eq!(&*bytes, b"base c");
+    assert!(Store::<str>::fetch_bytes(&mut overlay, "b.txt").is_err());
+
+    assert_eq!(
+        overlay.list_all("").unwrap(),
+        vec!["a.txt", "c.txt", "d.txt"]
+    );
+    // Whiteout markers are checked without fetching them.
+    assert!(log.ids().iter().all(|id| !id.contains(".wh.")));
+}
+
+#[test]
+fn store_routing() {
+    use id::AssetId;
+    use store::MemoryStore;
+    use {Assets, StoreOptions};
+
+    let base = MemoryStore::<AssetId>::new()
+        .with("ui/button.txt".into(), &b"base button"[..])
+        .with("level.txt".into(), &b"base level"[..]);
+    let ui = MemoryStore::<AssetId>::new()
+        .with("ui/button.txt".into(), &b"ui button"[..])
+        .with("level.txt".into(), &b"ui level"[..]);
+
+    let mut manager = Assets::<AssetId>::new()
+        .with_store(base)
+        .with_store_with_options(
+            ui,
+            StoreOptions::new().with_priority(1).with_prefix("ui/"),
+        )
+        .with_store_with_options(
+            MemoryStore::<AssetId>::new(),
+            StoreOptions::new().with_scheme("http"),
+        )
+        .with_loader(StringLoader);
+
+    assert_eq!(
+        &*manager.load::<String, _>("ui/button.txt".into(), ()).unwrap(),
+        "ui button"
+    );
+    assert_eq!(
+        &*manager.load::<String, _>("level.txt".into(), ()).unwrap(),
+        "base level"
+    );
+
+    assert!(
+        manager
+            .load::<String, _>("http://missing.txt".into(), ())
+            .is_err()
+    );
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn decompress_store() {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use id::AssetId;
+    use store::{DecompressStore, MemoryStore, RecordingStore, Store};
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(LICENSE_MIT.as_bytes()).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let memory = MemoryStore::new()
+        .with("LICENSE-MIT.gz".into(), compressed.clone())
+        .with("license".into(), compressed.clone())
+        .with("plain.txt".into(), &b"plain"[..]);
+    let recording = RecordingStore::new(memory);
+    let log = recording.log();
+    let mut store = DecompressStore::new(recording);
+
+    for id in &["LICENSE-MIT", "LICENSE-MIT.gz", "license"] {
+        assert_eq!(
+            LICENSE_MIT,
+            store
+                .fetch(&AssetId::new(id))
+                .map(|r| StringLoader.load((), r).unwrap())
+                .unwrap()
+        );
+    }
+    assert_eq!(
+        "plain",
+        store
+            .fetch(&"plain.txt".into())
+            .map(|r| StringLoader.load((), r).unwrap())
+            .unwrap()
+    );
+    assert!(store.fetch(&"missing.txt".into()).is_err());
+
+    // Metadata describes stored data and never requires decompression.
+    log.clear();
+    for id in &["LICENSE-MIT", "LICENSE-MIT.gz", "license"] {
+        let metadata = store.metadata(&AssetId::new(id)).unwrap().unwrap();
+        assert_eq!(metadata.size(), compressed.len() as u64);
+    }
+    assert!(store.metadata(&"missing.txt".into()).is_err());
+    assert!(log.fetches().is_empty());
+}
+
+#[cfg(feature = "sealed")]
+#[test]
+fn sealed_store() {
+    use std::io::ErrorKind;
+    use id::AssetId;
+    use store::{IntegrityError, MemoryStore, SealKey, SealedStore, Store};
+
+    let key = SealKey::new([42; 32]);
+    let sealed = key.seal("LICENSE-MIT", LICENSE_MIT.as_bytes());
+    assert_eq!(sealed, key.seal("./LICENSE-MIT", LICENSE_MIT.as_bytes()));
+
+    let mut tampered = sealed.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+
+    let memory = MemoryStore::new()
+        .with("LICENSE-MIT".into(), sealed.clone())
+        .with("renamed".into(), sealed)
+        .with("tampered".into(), tampered);
+    let mut store = SealedStore::new(memory, key);
+
+    assert_eq!(
+        LICENSE_MIT,
+        store
+            .fetch(&"LICENSE-MIT".into())
+            .map(|r| StringLoader.load((), r).unwrap())
+            .unwrap()
+    );
+
+    for id in &["renamed", "tampered"] {
+        let err = store.fetch(&AssetId::new(id)).unwrap_err();
+        let err = err.downcast_ref::<::std::io::Error>().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.get_ref().unwrap().is::<IntegrityError>());
+    }
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn http_store() {
+    use std::fs::remove_dir_all;
+    use std::io::{BufRead, BufReader, ErrorKind, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::{Duration, UNIX_EPOCH};
+    use store::{HttpStore, Store};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server = thread::spawn(move || {
+        let mut statuses = Vec::new();
+        for stream in listener.incoming().take(5) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                request.push_str(&line);
+            }
+
+            let response = if request.starts_with("HEAD /assets/LICENSE-MIT ") {
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nLast-Modified: {}\r\n\r\n",
+                    LICENSE_MIT.len(),
+                    "Fri, 01 Jun 2018 12:30:00 GMT"
+                )
+            } else if request.starts_with("GET /assets/truncated ") {
+                // Connection is closed in the middle of the chunk.
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n10\r\nshort".to_owned()
+            } else if !request.starts_with("GET /assets/LICENSE-MIT ") {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_owned()
+            } else if request.contains("If-None-Match: \"v1\"") {
+                "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\n\r\n".to_owned()
+            } else {
+                format!(
+                    "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\n\r\n{}",
+                    LICENSE_MIT.len(),
+                    LICENSE_MIT
+                )
+            };
+            statuses.push(response[9..12].to_owned());
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+        statuses
+    });
+
+    let cache = ::std::env::temp_dir().join("asset-http-store-test");
+    let _ = remove_dir_all(&cache);
+    let mut store = HttpStore::new(&format!("http://127.0.0.1:{}/assets/", port))
+        .unwrap()
+        .with_cache_dir(&cache);
+
+    for _ in 0..2 {
+        assert_eq!(
+            String::from(LICENSE_MIT),
+            store
+                .fetch("LICENSE-MIT")
+                .and_then(|r| StringLoader.load((), r))
+                .unwrap()
+        );
+    }
+    assert_eq!(
+        store.fetch("missing").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+
+    let metadata = store.metadata("LICENSE-MIT").unwrap().unwrap();
+    assert_eq!(metadata.size(), LICENSE_MIT.len() as u64);
+    assert_eq!(
+        metadata.modified(),
+        Some(UNIX_EPOCH + Duration::from_secs(1_527_856_200))
+    );
+    assert_eq!(
+        store.fetch("truncated").unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert!(!cache.join("data").join("truncated").exists());
+
+    assert_eq!(
+        server.join().unwrap(),
+        vec!["200", "304", "404", "200", "200"]
+    );
+
+    // Server is gone. Cached data is served.
+    assert_eq!(
+        String::from(LICENSE_MIT),
+        store
+            .fetch("LICENSE-MIT")
+            .and_then(|r| StringLoader.load((), r))
+            .unwrap()
+    );
+}
+
+#[cfg(all(feature = "content", feature = "fs"))]
+#[test]
+fn content_store() {
+    use id::AssetId;
+    use std::fs::remove_dir_all;
+    use std::path::PathBuf;
+    use store::{ContentHash, ContentStore, ContentWriter, MemoryStore, Metadata, Store};
+    use Assets;
+
+    let dir = ::std::env::temp_dir().join("asset-content-store-test");
+    let _ = remove_dir_all(&dir);
+
+    let mut writer = ContentWriter::new(&dir);
+    let hash = writer.add("LICENSE-MIT", LICENSE_MIT.as_bytes()).unwrap();
+    writer.add("copy/LICENSE-MIT", LICENSE_MIT.as_bytes()).unwrap();
+    writer.add("other", b"other").unwrap();
+    let manifest = writer.finish().unwrap();
+
+    assert_eq!(manifest.len(), 3);
+    assert_eq!(manifest.get("copy/LICENSE-MIT"), Some(hash));
+    assert_eq!(ContentHash::from_hex(&hash.to_hex()), Some(hash));
+    assert!(dir.join(&hash.to_hex()[..2]).join(hash.to_hex()).is_file());
+
+    let mut store = ContentStore::open(&dir).unwrap();
+    assert_eq!(store.manifest(), &manifest);
+    assert_eq!(
+        String::from(LICENSE_MIT),
+        store
+            .fetch(&AssetId::new("copy/LICENSE-MIT"))
+            .map(|r| StringLoader.load((), r).unwrap())
+            .unwrap()
+    );
+    assert!(store.fetch(&AssetId::new("missing")).is_err());
+
+    // Store that reports hash of content other than it serves.
+    struct Mislabeled(MemoryStore<PathBuf>);
+
+    impl Store<PathBuf> for Mislabeled {
+        type Error = ::std::io::Error;
+        type Reader = <MemoryStore<PathBuf> as Store<PathBuf>>::Reader;
+
+        const KIND: &'static str = "Mislabeled";
+
+        fn fetch(&mut self, id: &PathBuf) -> Result<Self::Reader, Self::Error> {
+            self.0.fetch(id)
+        }
+
+        fn metadata(&mut self, id: &PathBuf) -> Result<Option<Metadata>, Self::Error> {
+            let metadata = self.0.metadata(id)?;
+            Ok(metadata.map(|metadata| metadata.with_hash(ContentHash::of(b"something else"))))
+        }
+    }
+
+    let memory = MemoryStore::new().with("LICENSE-MIT".into(), LICENSE_MIT.as_bytes());
+    let mut assets = Assets::<PathBuf>::new()
+        .with_store(Mislabeled(memory))
+        .with_loader(StringLoader);
+    assert!(assets.load::<String, _>("LICENSE-MIT".into(), ()).is_ok());
+
+    let memory = MemoryStore::new().with("LICENSE-MIT".into(), LICENSE_MIT.as_bytes());
+    let mut assets = Assets::<PathBuf>::new()
+        .with_store(Mislabeled(memory))
+        .with_loader(StringLoader)
+        .with_verify(true);
+    assert!(assets.load::<String, _>("LICENSE-MIT".into(), ()).is_err());
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn store_metadata() {
+    use std::path::PathBuf;
+    use store::{FsStore, MemoryStore, Store};
+    use Assets;
+
+    let mut fs = FsStore::new().with_path(concat!(env!("CARGO_MANIFEST_DIR"), "/src"));
+    let metadata = Store::<str>::metadata(&mut fs, "tests/mod.rs")
+        .unwrap()
+        .unwrap();
+    assert_eq!(metadata.size(), include_str!("mod.rs").len() as u64);
+    assert!(metadata.modified().is_some());
+
+    let memory = MemoryStore::<PathBuf>::new().with("LICENSE-MIT".into(), LICENSE_MIT.as_bytes());
+    let mut assets = Assets::<PathBuf>::new().with_store(memory).with_store(fs);
+    assert_eq!(
+        assets
+            .metadata(&"LICENSE-MIT".into())
+            .unwrap()
+            .map(|metadata| metadata.size()),
+        Some(LICENSE_MIT.len() as u64)
+    );
+    assert!(assets.metadata(&"lib.rs".into()).unwrap().is_some());
+    assert!(assets.metadata(&"missing".into()).is_err());
+
+    // Store without metadata support doesn't shadow stores after it.
+    struct Plain(MemoryStore<PathBuf>);
+
+    impl Store<PathBuf> for Plain {
+        type Error = Error;
+        type Reader = <MemoryStore<PathBuf> as Store<PathBuf>>::Reader;
+
+        const KIND: &'static str = "Plain";
+
+        fn fetch(&mut self, id: &PathBuf) -> Result<Self::Reader, Error> {
+            self.0.fetch(id)
+        }
+    }
+
+    let plain = MemoryStore::<PathBuf>::new().with("plain".into(), &b"plain"[..]);
+    let memory = MemoryStore::<PathBuf>::new().with("LICENSE-MIT".into(), LICENSE_MIT.as_bytes());
+    let mut assets = Assets::<PathBuf>::new()
+        .with_store(Plain(plain))
+        .with_store(memory);
+    assert!(assets.metadata(&"missing".into()).is_err());
+    assert!(assets.metadata(&"plain".into()).unwrap().is_none());
+    assert_eq!(
+        assets
+            .metadata(&"LICENSE-MIT".into())
+            .unwrap()
+            .map(|metadata| metadata.size()),
+        Some(LICENSE_MIT.len() as u64)
+    );
+}
+
+#[test]
+fn seekable_loading() {
+    use std::path::PathBuf;
+    use store::{MemoryStore, OverlayStore};
+    use Assets;
+
+    let memory = MemoryStore::<PathBuf>::new().with("LICENSE-MIT".into(), LICENSE_MIT.as_bytes());
+    let mut assets = Assets::<PathBuf>::new()
+        .with_store(memory.clone())
+        .with_loader(StringLoader);
+    assert_eq!(
+        &*assets
+            .load_seekable::<String, _>("LICENSE-MIT".into(), ())
+            .unwrap(),
+        LICENSE_MIT
+    );
+
+    // Overlay can't provide seekable readers, data is buffered.
+    let overlay = OverlayStore::new().with_layer("memory", 0, memory);
+    let mut assets = Assets::<PathBuf>::new()
+        .with_store(overlay)
+        .with_loader(StringLoader);
+    assert_eq!(
+        &*assets
+            .load_bytes::<String, _>("LICENSE-MIT".into(), ())
+            .unwrap(),
+        LICENSE_MIT
+    );
+}
+
+#[cfg(all(feature = "fs", feature = "memmap"))]
+#[test]
+fn memory_mapped_store() {
+    use store::{FsStore, Store};
+
+    let mut fs = FsStore::new().with_path(concat!(env!("CARGO_MANIFEST_DIR"), "/src"));
+    assert!(Store::<str>::fetch_bytes(&mut fs, "tests/mod.rs").unwrap().is_none());
+
+    // Files of the crate are not modified while tests run.
+    let mut fs = unsafe { fs.with_mmap(true) };
+    let bytes = Store::<str>::fetch_bytes(&mut fs, "tests/mod.rs")
+        .unwrap()
+        .unwrap();
+    assert!(bytes.is_mapped());
+    assert_eq!(&*bytes, include_bytes!("mod.rs"));
+}
+
+#[test]
+fn store_listing() {
+    use id::AssetId;
+    use path::Glob;
+    use store::MemoryStore;
+    use Assets;
+
+    let glob = Glob::new("./sprites/**/?.png");
+    assert_eq!(glob.dir(), "sprites");
+    assert!(glob.matches("sprites/a.png"));
+    assert!(glob.matches("sprites/ui/b.png"));
+    assert!(!glob.matches("sprites/ab.png"));
+    assert!(!Glob::new("sprites/*.png").matches("sprites/ui/b.png"));
+    assert!(Glob::new("**/b.png").matches("b.png"));
+    assert!(Glob::new("**b.png").matches("sprites/ui/b.png"));
+
+    // Backtracking over many stars must not take exponential time.
+    let long = "a".repeat(64);
+    assert!(!Glob::new("a*a*a*a*a*a*a*a*a*a*a*a*b").matches(&long));
+    assert!(!Glob::new("**a**a**a**a**a**a**a**a**b").matches(&long));
+
+    let memory = MemoryStore::<AssetId>::new()
+        .with("sprites/a.png".into(), &b"a"[..])
+        .with("sprites/b.png".into(), &b"b"[..])
+        .with("sprites/ui/c.png".into(), &b"c"[..])
+        .with("sprites.png".into(), &b"other"[..]);
+    let mut assets = Assets::<AssetId>::new()
+        .with_store(memory)
+        .with_loader(StringLoader);
+
+    assert_eq!(
+        assets.list("sprites").unwrap(),
+        vec!["sprites/a.png", "sprites/b.png", "sprites/ui/c.png"]
+    );
+
+    let sprites = assets.load_glob::<String, _>("sprites/*.png", ()).unwrap();
+    let sprites: Vec<&str> = sprites.iter().map(|sprite| sprite.as_str()).collect();
+    assert_eq!(sprites, vec!["a", "b"]);
+
+    assert_eq!(
+        assets
+            .load_glob::<String, _>("sprites/**/*.png", ())
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+#[cfg(all(feature = "fs", feature = "serde", feature = "ron", feature = "sprite"))]
+#[test]
+fn asset_saving() {
+    use std::fs::{read_dir, remove_dir_all};
+    use std::path::PathBuf;
+    use store::{AssetSink, FsStore, MemoryStore, Store};
+    use {AnimationSet, Assets, RonFormat, SerdeLoader};
+
+    let dir = ::std::env::temp_dir().join("asset-saving-test");
+    let _ = remove_dir_all(&dir);
+    let mut fs = FsStore::new().with_path(&dir);
+    fs.write("levels/first.txt", b"old").unwrap();
+    fs.write("levels/first.txt", LICENSE_MIT.as_bytes()).unwrap();
+    assert_eq!(
+        String::from(LICENSE_MIT),
+        fs.fetch("levels/first.txt")
+            .and_then(|r| StringLoader.load((), r))
+            .unwrap()
+    );
+    assert_eq!(read_dir(dir.join("levels")).unwrap().count(), 1);
+    for path in &["../escape.txt", "/tmp/escape.txt"] {
+        assert_eq!(
+            fs.write(path, b"escape").unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::symlink;
+
+        // Nothing is created through symlinked directory leading outside of the root.
+        let outside = ::std::env::temp_dir().join("asset-saving-test-outside");
+        let _ = remove_dir_all(&outside);
+        ::std::fs::create_dir_all(&outside).unwrap();
+        symlink(&outside, dir.join("link")).unwrap();
+        let mut strict = FsStore::new().with_path(&dir).with_strict(true);
+        assert_eq!(
+            strict.write("link/sub/escape.txt", b"escape").unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+        assert!(!outside.join("sub").exists());
+        strict.write("levels/second.txt", b"second").unwrap();
+
+        // Listing skips cyclic symlinks, temporary files and paths outside of the root.
+        symlink(&dir, dir.join("levels").join("loop")).unwrap();
+        ::std::fs::write(dir.join("levels").join(".first.txt.1.2.tmp"), b"temp").unwrap();
+        ::std::fs::write(outside.join("outside.txt"), b"outside").unwrap();
+        symlink(outside.join("outside.txt"), dir.join("outside.txt")).unwrap();
+        let listed = vec!["levels/first.txt", "levels/second.txt", "outside.txt"];
+        assert_eq!(Store::<str>::list(&mut fs, "").unwrap(), listed);
+        assert_eq!(
+            Store::<str>::list(&mut strict, "").unwrap(),
+            listed[..2].to_vec()
+        );
+        assert_eq!(
+            Store::<str>::list(&mut strict, "link").unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+    }
+
+    let memory = MemoryStore::<PathBuf>::new();
+    let mut assets = Assets::<PathBuf>::new()
+        .with_store(memory.clone())
+        .with_sink(memory)
+        .with_loader(SerdeLoader);
+
+    let set = AnimationSet::new()
+        .with_animation(String::from("walk"), 0..4)
+        .with_animation(String::from("jump"), 4..6);
+    assets.save("anim.ron".into(), &set, RonFormat).unwrap();
+
+    let loaded = assets
+        .load::<AnimationSet<String>, _>("anim.ron".into(), RonFormat)
+        .unwrap();
+    assert_eq!(*loaded, set);
+}
+
+#[cfg(all(feature = "futures", feature = "fs", feature = "serde", feature = "ron"))]
+#[test]
+fn async_fs_store() {
+    use futures::executor::block_on;
+    use std::collections::HashMap;
+    use std::fs::{create_dir_all, File};
+    use std::io::Write;
+    use store::{AsyncFsStore, AsyncStore, FsStore};
+    use {AsyncAssetLoader, RonFormat, SerdeLoader};
+
+    let dir = ::std::env::temp_dir().join("asset-async-fs-test");
+    create_dir_all(&dir).unwrap();
+    let mut values = HashMap::new();
+    for index in 0..20000u32 {
+        values.insert(format!("value{}", index), index);
+    }
+    File::create(dir.join("values.ron"))
+        .unwrap()
+        .write_all(ron::ser::to_string(&values).unwrap().as_bytes())
+        .unwrap();
+
+    let mut store = AsyncFsStore::new(FsStore::new().with_path(&dir), 2);
+
+    let reader = store.fetch_async("values.ron").unwrap();
+    let (_, loaded): (_, HashMap<String, u32>) =
+        block_on(SerdeLoader.load_async(RonFormat, reader)).ok().unwrap();
+    assert_eq!(loaded, values);
+
+    let reader = store.fetch_async("missing.ron").unwrap();
+    let result: Result<(_, HashMap<String, u32>), _> =
+        block_on(SerdeLoader.load_async(RonFormat, reader));
+    assert!(result.is_err());
+
+    // Reader that is not polled must not hold the only thread of the pool.
+    let mut store = AsyncFsStore::new(FsStore::new().with_path(&dir), 1);
+    let stalled = store.fetch_async("values.ron").unwrap();
+    let reader = store.fetch_async("values.ron").unwrap();
+    let (_, loaded): (_, HashMap<String, u32>) =
+        block_on(SerdeLoader.load_async(RonFormat, reader)).ok().unwrap();
+    assert_eq!(loaded, values);
+    let (_, loaded): (_, HashMap<String, u32>) =
+        block_on(SerdeLoader.load_async(RonFormat, stalled)).ok().unwrap();
+    assert_eq!(loaded, values);
+}
+
+#[test]
+fn caching_store() {
+    use id::AssetId;
+    use std::cell::{Cell, RefCell};
+    use std::fs::remove_dir_all;
+    use std::io::Cursor;
+    use std::rc::Rc;
+    use store::{CachingStore, Metadata, Store};
+
+    #[derive(Clone)]
+    struct Slow {
+        data: Rc<RefCell<Vec<u8>>>,
+        fetches: Rc<Cell<usize>>,
+    }
+
+    impl Store<AssetId> for Slow {
+        type Error = Error;
+        type Reader = Cursor<Vec<u8>>;
+
+        const KIND: &'static str = "Slow";
+
+        fn fetch(&mut self, _id: &AssetId) -> Result<Cursor<Vec<u8>>, Error> {
+            self.fetches.set(self.fetches.get() + 1);
+            Ok(Cursor::new(self.data.borrow().clone()))
+        }
+
+        fn metadata(&mut self, _id: &AssetId) -> Result<Option<Metadata>, Error> {
+            Ok(Some(Metadata::new(self.data.borrow().len() as u64)))
+        }
+    }
+
+    let slow = Slow {
+        data: Rc::new(RefCell::new(b"first".to_vec())),
+        fetches: Rc::new(Cell::new(0)),
+    };
+    let dir = ::std::env::temp_dir().join("asset-caching-test");
+    let _ = remove_dir_all(&dir);
+
+    let mut store = CachingStore::new(slow.clone(), 8).with_cache_dir(&dir);
+    assert_eq!(&*store.find(&AssetId::new("a")).unwrap(), b"first");
+    assert_eq!(&*store.find(&AssetId::new("a")).unwrap(), b"first");
+    assert_eq!(slow.fetches.get(), 1);
+
+    // Assets are refetched when metadata changes.
+    *slow.data.borrow_mut() = b"second".to_vec();
+    assert_eq!(&*store.find(&AssetId::new("a")).unwrap(), b"second");
+    assert_eq!(slow.fetches.get(), 2);
+
+    // Least recently used asset is evicted when capacity is exceeded.
+    store.find(&AssetId::new("b")).unwrap();
+    assert_eq!(store.size(), 6);
+    store.clear();
+
+    // Evicted assets are found in disk cache.
+    assert_eq!(&*store.find(&AssetId::new("a")).unwrap(), b"second");
+    assert_eq!(slow.fetches.get(), 3);
+    let mut store = CachingStore::new(slow.clone(), 8).with_cache_dir(&dir);
+    assert_eq!(&*store.find(&AssetId::new("a")).unwrap(), b"second");
+    assert_eq!(&*store.find(&AssetId::new("b")).unwrap(), b"second");
+    assert_eq!(slow.fetches.get(), 3);
+
+    // Data of stores without metadata is never cached on disk.
+    struct Plain(Slow);
+
+    impl Store<AssetId> for Plain {
+        type Error = Error;
+        type Reader = Cursor<Vec<u8>>;
+
+        const KIND: &'static str = "Plain";
+
+        fn fetch(&mut self, id: &AssetId) -> Result<Cursor<Vec<u8>>, Error> {
+            self.0.fetch(id)
+        }
+    }
+
+    let mut store = CachingStore::new(Plain(slow.clone()), 8).with_cache_dir(&dir);
+    assert_eq!(&*store.find(&AssetId::new("c")).unwrap(), b"second");
+    assert_eq!(&*store.find(&AssetId::new("c")).unwrap(), b"second");
+    assert_eq!(slow.fetches.get(), 4);
+    assert!(!dir.join("data").join("c").exists());
+}
+
+#[test]
+fn store_faults() {
+    use id::AssetId;
+    use std::time::{Duration, Instant};
+    use store::{Fault, FetchKind, MockStore, RecordingStore, Store};
+    use Assets;
+
+    let id = AssetId::new("LICENSE-MIT");
+    let broken = MockStore::new()
+        .with(id, LICENSE_MIT.as_bytes())
+        .with_fault(id, Fault::Error(ErrorKind::PermissionDenied));
+    let missing = MockStore::new().with_fault(id, Fault::NotFound);
+    let recording = RecordingStore::new(missing.clone());
+    let log = recording.log();
+
+    let mut assets = Assets::<AssetId>::new()
+        .with_store(broken.clone())
+        .with_store(recording)
+        .with_loader(StringLoader);
+
+    // Errors of all stores are reported.
+    let err = assets.load::<String, _>(id, ()).unwrap_err();
+    let messages: Vec<String> = err.iter_chain().map(|cause| cause.to_string()).collect();
+    assert!(messages.iter().any(|m| m.contains("Fault injected by MockStore")));
+    assert!(messages.iter().any(|m| m.contains("entity not found")));
+    assert_eq!(log.ids(), vec!["LICENSE-MIT"]);
+    assert_eq!(log.fetches()[0].kind(), FetchKind::Read);
+    assert!(!log.fetches()[0].is_ok());
+
+    // Next store is tried when one fails.
+    missing.clear_faults(&id);
+    missing.insert(id, &b"fallback"[..]);
+    assert_eq!(&*assets.load::<String, _>(id, ()).unwrap(), "fallback");
+    assert!(log.fetches()[1].is_ok());
+
+    // Failure in the middle of reading fails loading.
+    let id = AssetId::new("LICENSE-APACHE");
+    broken.insert(id, LICENSE_MIT.as_bytes());
+    broken.add_fault(id, Fault::ErrorAfter(10, ErrorKind::ConnectionReset));
+    let err = assets.load::<String, _>(id, ()).unwrap_err();
+    assert!(err.to_string().contains("Failed to load asset"));
+    assert_eq!(log.ids().len(), 2);
+
+    let id = AssetId::new("truncated");
+    broken.insert(id, LICENSE_MIT.as_bytes());
+    broken.add_fault(id, Fault::Delay(Duration::from_millis(20)));
+    broken.add_fault(id, Fault::Truncate(9));
+    let start = Instant::now();
+    assert_eq!(&*assets.load::<String, _>(id, ()).unwrap(), "Copyright");
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    // Metadata requests follow the same script.
+    let mut store = broken.clone();
+    let start = Instant::now();
+    assert_eq!(store.metadata(&id).unwrap().unwrap().size(), 9);
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert_eq!(
+        store.metadata(&AssetId::new("LICENSE-MIT")).unwrap_err().kind(),
+        ErrorKind::PermissionDenied
+    );
+    assert_eq!(
+        store.metadata(&AssetId::new("LICENSE-APACHE")).unwrap().unwrap().size(),
+        LICENSE_MIT.len() as u64
+    );
+}
+
+#[cfg(all(feature = "serde", feature = "ron"))]
+#[test]
+fn asset_redirects() {
+    use id::AssetId;
+    use store::MemoryStore;
+    use {Assets, Redirects};
+
+    let redirects = Redirects::<AssetId>::from_reader(
+        &br#"{
+            "LICENSE": "licenses/MIT-old",
+            "licenses/MIT-old": "licenses/MIT",
+            "a": "b",
+            "b": "a",
+        }"#[..],
+    ).unwrap();
+    assert_eq!(
+        redirects.resolve(&AssetId::new("LICENSE")).unwrap(),
+        Some(&AssetId::new("licenses/MIT"))
+    );
+    assert_eq!(redirects.resolve(&AssetId::new("licenses/MIT")).unwrap(), None);
+    assert!(redirects.resolve(&AssetId::new("a")).is_err());
+
+    let mut assets = Assets::<AssetId>::new()
+        .with_store(MemoryStore::new().with(AssetId::new("licenses/MIT"), LICENSE_MIT.as_bytes()))
+        .with_redirects(redirects)
+        .with_loader(StringLoader);
+
+    let old = assets.load::<String, _>(AssetId::new("LICENSE"), ()).unwrap();
+    let new = assets.load::<String, _>(AssetId::new("licenses/MIT"), ()).unwrap();
+    assert_eq!(old, new);
+    assert!(assets.metadata(&AssetId::new("LICENSE")).unwrap().is_some());
+
+    let err = assets.load::<String, _>(AssetId::new("a"), ()).unwrap_err();
+    assert!(err.to_string().contains("cycle"));
+}